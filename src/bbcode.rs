@@ -0,0 +1,533 @@
+use crate::{
+    post_body::{CodeKind, Node, PostBody},
+    Error,
+};
+
+/// Converts the inner HTML of a post body, such as Post::post_body, to the
+/// BBCode that produces it. The edit notice is generated by the forums and is
+/// not included.
+pub fn from_html(html: &str) -> String {
+    from_post_body(&PostBody::parse(html))
+}
+
+/// Converts a parsed post body to the BBCode that produces it.
+pub fn from_post_body(body: &PostBody) -> String {
+    let mut out = String::new();
+    write_nodes(&body.nodes, &mut out);
+    out
+}
+
+fn write_nodes(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        write_node(node, out);
+    }
+}
+
+fn write_tag(tag: &str, children: &[Node], out: &mut String) {
+    out.push_str(&format!("[{tag}]"));
+    write_nodes(children, out);
+    out.push_str(&format!("[/{tag}]"));
+}
+
+fn write_node(node: &Node, out: &mut String) {
+    match node {
+        Node::Paragraph(children) => write_nodes(children, out),
+        Node::Text(text) => write_text(text, out),
+        Node::LineBreak => out.push('\n'),
+        Node::Bold(children) => write_tag("b", children, out),
+        Node::Italic(children) => write_tag("i", children, out),
+        Node::Underline(children) => write_tag("u", children, out),
+        Node::Strikethrough(children) => write_tag("s", children, out),
+        Node::Superscript(children) => write_tag("super", children, out),
+        Node::Subscript(children) => write_tag("sub", children, out),
+        Node::Fixed(children) => write_tag("fixed", children, out),
+        Node::Spoiler(children) => write_tag("spoiler", children, out),
+        Node::Quote {
+            author,
+            post_id,
+            children,
+        } => {
+            // Attributions that cannot be written are left out, so that the
+            // quote itself is still balanced.
+            let author = author.as_deref().filter(|x| is_legal_attribute(x));
+            let post_id = post_id.as_deref().filter(|x| is_legal_attribute(x));
+            match (author, post_id) {
+                (Some(author), Some(post_id)) => {
+                    out.push_str(&format!(r#"[quote="{author}" post="{post_id}"]"#))
+                }
+                (Some(author), None) => out.push_str(&format!(r#"[quote="{author}"]"#)),
+                (None, _) => out.push_str("[quote]"),
+            }
+            write_nodes(children, out);
+            out.push_str("[/quote]");
+        }
+        Node::Code { kind, text } => {
+            let tag = match kind {
+                CodeKind::Code => "code",
+                CodeKind::Php => "php",
+                CodeKind::Pre => "pre",
+            };
+            write_verbatim(tag, text, out);
+        }
+        Node::Image { url, thumbnail } => {
+            let tag = if *thumbnail { "timg" } else { "img" };
+            out.push_str(&format!("[{tag}]{}[/{tag}]", encode_url(url)));
+        }
+        Node::Link { url, children } => match children.as_slice() {
+            [Node::Text(text)] if text == url => {
+                out.push_str(&format!("[url]{}[/url]", encode_url(url)))
+            }
+            _ => {
+                out.push_str(&format!("[url={}]", encode_url(url)));
+                write_nodes(children, out);
+                out.push_str("[/url]");
+            }
+        },
+        Node::Video { url } => {
+            out.push_str(&format!("[video]{}[/video]", encode_url(&video_url(url))))
+        }
+        Node::Smilie { code, .. } => out.push_str(code),
+        Node::List { ordered, items } => {
+            out.push_str(if *ordered { "[list=1]" } else { "[list]" });
+            for item in items {
+                out.push_str("\n[*]");
+                write_nodes(item, out);
+            }
+            out.push_str("\n[/list]");
+        }
+    }
+}
+
+/// Writes text that the forums show literally. Text that could be read as
+/// tags is wrapped in [noparse].
+fn write_text(text: &str, out: &mut String) {
+    let Some(start) = text.find('[') else {
+        out.push_str(text);
+        return;
+    };
+    let end = match text.rfind(']') {
+        Some(end) if text.rfind('[') < Some(end) => end + 1,
+        _ => text.len(),
+    };
+    out.push_str(&text[..start]);
+
+    // A literal [/noparse] would end the noparse early, so split it after
+    // its opening bracket.
+    let mut rest = &text[start..end];
+    while !rest.is_empty() {
+        let split = rest
+            .to_ascii_lowercase()
+            .find("[/noparse]")
+            .map_or(rest.len(), |split| split + 1);
+        let (chunk, tail) = rest.split_at(split);
+        if chunk.contains('[') {
+            out.push_str(&format!("[noparse]{chunk}[/noparse]"));
+        } else {
+            out.push_str(chunk);
+        }
+        rest = tail;
+    }
+    out.push_str(&text[end..]);
+}
+
+/// Writes text that is shown verbatim within a tag. A literal closing tag
+/// would end the block early, so the block is closed around it and the tag is
+/// written in [noparse].
+fn write_verbatim(tag: &str, text: &str, out: &mut String) {
+    let closing = format!("[/{tag}]");
+    out.push_str(&format!("[{tag}]"));
+    let mut rest = text;
+    while let Some(start) = rest.to_ascii_lowercase().find(&closing) {
+        let end = start + closing.len();
+        out.push_str(&rest[..start]);
+        out.push_str(&format!(
+            "[/{tag}][noparse]{}[/noparse][{tag}]",
+            &rest[start..end]
+        ));
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out.push_str(&closing);
+}
+
+/// Percent-encodes the characters of a URL that would end its tag early.
+fn encode_url(url: &str) -> String {
+    url.replace('[', "%5B")
+        .replace(']', "%5D")
+        .replace('"', "%22")
+}
+
+/// Returns whether a value can be written as a quoted tag attribute.
+fn is_legal_attribute(value: &str) -> bool {
+    !value.contains(['"', ']'])
+}
+
+/// Finds quote attributions that cannot be written as attributes.
+fn check_attributes(nodes: &[Node], issues: &mut Vec<ValidationIssue>) {
+    for node in nodes {
+        match node {
+            Node::Quote {
+                author,
+                post_id,
+                children,
+            } => {
+                for value in [author, post_id].into_iter().flatten() {
+                    if !is_legal_attribute(value) {
+                        issues.push(ValidationIssue::IllegalAttribute {
+                            tag: String::from("quote"),
+                            value: value.clone(),
+                        });
+                    }
+                }
+                check_attributes(children, issues);
+            }
+            Node::Paragraph(children)
+            | Node::Bold(children)
+            | Node::Italic(children)
+            | Node::Underline(children)
+            | Node::Strikethrough(children)
+            | Node::Superscript(children)
+            | Node::Subscript(children)
+            | Node::Fixed(children)
+            | Node::Spoiler(children)
+            | Node::Link { children, .. } => check_attributes(children, issues),
+            Node::List { items, .. } => {
+                for item in items {
+                    check_attributes(item, issues);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the URL that was posted to embed a video. YouTube videos are
+/// posted as watch links but embedded with a different URL.
+fn video_url(url: &str) -> String {
+    const YOUTUBE_EMBED: [&str; 2] = [
+        "https://www.youtube.com/embed/",
+        "https://www.youtube-nocookie.com/embed/",
+    ];
+    for prefix in YOUTUBE_EMBED {
+        if let Some(id) = url.strip_prefix(prefix) {
+            let id = id.split(['?', '&']).next().unwrap_or_default();
+            return format!("https://www.youtube.com/watch?v={id}");
+        }
+    }
+    url.to_owned()
+}
+
+/// Composes BBCode from typed elements, so that tags are always balanced.
+/// Text is shown literally, even if it looks like tags, and URLs are encoded
+/// so that they cannot end their tags early.
+///
+/// ```
+/// use something_awful::bbcode::BBCode;
+///
+/// let message = BBCode::new()
+///     .quote_post("Somebody", "123", BBCode::new().text("first!"))
+///     .bold(BBCode::new().text("no"))
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     message,
+///     r#"[quote="Somebody" post="123"]first![/quote][b]no[/b]"#
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BBCode {
+    nodes: Vec<Node>,
+}
+
+impl BBCode {
+    /// Creates empty BBCode.
+    pub fn new() -> BBCode {
+        BBCode { nodes: Vec::new() }
+    }
+
+    /// Appends plain text. Newlines become line breaks.
+    pub fn text(mut self, text: &str) -> Self {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.nodes.push(Node::LineBreak);
+            }
+            if !line.is_empty() {
+                self.nodes.push(Node::Text(line.to_owned()));
+            }
+        }
+        self
+    }
+
+    /// Appends a line break.
+    pub fn line_break(mut self) -> Self {
+        self.nodes.push(Node::LineBreak);
+        self
+    }
+
+    pub fn bold(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Bold(content.nodes));
+        self
+    }
+
+    pub fn italic(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Italic(content.nodes));
+        self
+    }
+
+    pub fn underline(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Underline(content.nodes));
+        self
+    }
+
+    pub fn strikethrough(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Strikethrough(content.nodes));
+        self
+    }
+
+    /// Appends monospaced inline text.
+    pub fn fixed(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Fixed(content.nodes));
+        self
+    }
+
+    /// Appends content that is hidden until hovered.
+    pub fn spoiler(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Spoiler(content.nodes));
+        self
+    }
+
+    /// Appends a quote without attribution.
+    pub fn quote(mut self, content: BBCode) -> Self {
+        self.nodes.push(Node::Quote {
+            author: None,
+            post_id: None,
+            children: content.nodes,
+        });
+        self
+    }
+
+    /// Appends a quote attributed to a user.
+    pub fn quote_user(mut self, author: &str, content: BBCode) -> Self {
+        self.nodes.push(Node::Quote {
+            author: Some(author.to_owned()),
+            post_id: None,
+            children: content.nodes,
+        });
+        self
+    }
+
+    /// Appends a quote attributed to a user that links to the quoted post.
+    pub fn quote_post(mut self, author: &str, post_id: &str, content: BBCode) -> Self {
+        self.nodes.push(Node::Quote {
+            author: Some(author.to_owned()),
+            post_id: Some(post_id.to_owned()),
+            children: content.nodes,
+        });
+        self
+    }
+
+    /// Appends a block of code, which is shown verbatim.
+    pub fn code(mut self, code: &str) -> Self {
+        self.nodes.push(Node::Code {
+            kind: CodeKind::Code,
+            text: code.to_owned(),
+        });
+        self
+    }
+
+    /// Appends an image shown at full size.
+    pub fn image(mut self, url: &str) -> Self {
+        self.nodes.push(Node::Image {
+            url: url.to_owned(),
+            thumbnail: false,
+        });
+        self
+    }
+
+    /// Appends an image shown shrunk until clicked.
+    pub fn thumbnail(mut self, url: &str) -> Self {
+        self.nodes.push(Node::Image {
+            url: url.to_owned(),
+            thumbnail: true,
+        });
+        self
+    }
+
+    /// Appends a link that displays its URL.
+    pub fn url(mut self, url: &str) -> Self {
+        self.nodes.push(Node::Link {
+            url: url.to_owned(),
+            children: vec![Node::Text(url.to_owned())],
+        });
+        self
+    }
+
+    /// Appends a link that displays the given content.
+    pub fn link(mut self, url: &str, content: BBCode) -> Self {
+        self.nodes.push(Node::Link {
+            url: url.to_owned(),
+            children: content.nodes,
+        });
+        self
+    }
+
+    /// Appends a bulleted list.
+    pub fn list(mut self, items: Vec<BBCode>) -> Self {
+        self.nodes.push(Node::List {
+            ordered: false,
+            items: items.into_iter().map(|x| x.nodes).collect(),
+        });
+        self
+    }
+
+    /// Appends a numbered list.
+    pub fn ordered_list(mut self, items: Vec<BBCode>) -> Self {
+        self.nodes.push(Node::List {
+            ordered: true,
+            items: items.into_iter().map(|x| x.nodes).collect(),
+        });
+        self
+    }
+
+    /// Consumes the builder, returning the BBCode message. Returns
+    /// InvalidBBCode if a quote is attributed to an author or post ID that
+    /// contains a double quote or closing bracket, which cannot be written.
+    pub fn build(self) -> Result<String, Error> {
+        let mut issues = Vec::new();
+        check_attributes(&self.nodes, &mut issues);
+        if !issues.is_empty() {
+            return Err(Error::InvalidBBCode(issues));
+        }
+        let mut out = String::new();
+        write_nodes(&self.nodes, &mut out);
+        Ok(out)
+    }
+}
+
+/// Tags whose contents are shown verbatim, so tags within them are not
+/// parsed.
+const VERBATIM_TAGS: [&str; 4] = ["code", "php", "pre", "noparse"];
+
+/// Tags that must be closed.
+const PAIRED_TAGS: [&str; 21] = [
+    "b", "i", "u", "s", "super", "sub", "fixed", "spoiler", "quote", "code", "php", "pre",
+    "noparse", "img", "timg", "url", "email", "video", "list", "color", "size",
+];
+
+/// Describes a problem that would cause a message to render incorrectly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationIssue {
+    /// A tag opened at the given byte offset is never closed.
+    UnclosedTag { tag: String, offset: usize },
+
+    /// A tag closed at the given byte offset was never opened.
+    UnexpectedClosingTag { tag: String, offset: usize },
+
+    /// The message has more images than allowed.
+    TooManyImages { count: usize, limit: usize },
+
+    /// A tag attribute has a value that cannot be written in BBCode.
+    IllegalAttribute { tag: String, value: String },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::UnclosedTag { tag, offset } => {
+                write!(f, "[{tag}] at offset {offset} is never closed")
+            }
+            ValidationIssue::UnexpectedClosingTag { tag, offset } => {
+                write!(f, "[/{tag}] at offset {offset} was never opened")
+            }
+            ValidationIssue::TooManyImages { count, limit } => {
+                write!(f, "{count} images exceeds the limit of {limit}")
+            }
+            ValidationIssue::IllegalAttribute { tag, value } => {
+                write!(f, "{value:?} cannot be used as an attribute of [{tag}]")
+            }
+        }
+    }
+}
+
+/// Checks BBCode messages for problems before they are posted.
+#[derive(Debug, Clone, Default)]
+pub struct Validator {
+    max_images: Option<usize>,
+}
+
+impl Validator {
+    /// Creates a validator that checks that tags are balanced.
+    pub fn new() -> Validator {
+        Validator { max_images: None }
+    }
+
+    /// Sets the maximum number of images, including thumbnails, that a message
+    /// may contain. Defaults to no limit.
+    pub fn with_max_images(mut self, max_images: usize) -> Self {
+        self.max_images = Some(max_images);
+        self
+    }
+
+    /// Returns all problems found in a message. The message is valid if there
+    /// are none.
+    pub fn validate(&self, message: &str) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut open: Vec<(String, usize)> = Vec::new();
+        let mut images = 0;
+
+        let mut rest = message;
+        while let Some(start) = rest.find('[') {
+            let offset = message.len() - rest.len() + start;
+            rest = &rest[start + 1..];
+            let Some(end) = rest.find(']') else {
+                break;
+            };
+            let tag = &rest[..end];
+            let (closing, name) = match tag.strip_prefix('/') {
+                Some(name) => (true, name),
+                None => (false, tag.split(['=', ' ']).next().unwrap_or_default()),
+            };
+            let name = name.to_ascii_lowercase();
+
+            // Within verbatim tags, only the matching closing tag is parsed.
+            if let Some((verbatim, _)) = open.last() {
+                if VERBATIM_TAGS.contains(&verbatim.as_str()) && !(closing && *verbatim == name) {
+                    continue;
+                }
+            }
+            if !PAIRED_TAGS.contains(&name.as_str()) {
+                continue;
+            }
+            rest = &rest[end + 1..];
+
+            if !closing {
+                if name == "img" || name == "timg" {
+                    images += 1;
+                }
+                open.push((name, offset));
+                continue;
+            }
+            match open.iter().rposition(|(tag, _)| *tag == name) {
+                Some(position) => {
+                    for (tag, offset) in open.drain(position..).skip(1) {
+                        issues.push(ValidationIssue::UnclosedTag { tag, offset });
+                    }
+                }
+                None => issues.push(ValidationIssue::UnexpectedClosingTag { tag: name, offset }),
+            }
+        }
+        for (tag, offset) in open {
+            issues.push(ValidationIssue::UnclosedTag { tag, offset });
+        }
+
+        if let Some(limit) = self.max_images {
+            if images > limit {
+                issues.push(ValidationIssue::TooManyImages {
+                    count: images,
+                    limit,
+                });
+            }
+        }
+        issues
+    }
+}
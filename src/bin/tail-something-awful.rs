@@ -1,14 +1,30 @@
 /// Tails your bookmarked Something Awful threads.
 use clap::Parser;
-use something_awful::client::{Client, ThreadPage, User};
+use something_awful::{
+    client::{Client, ThreadPage, User},
+    credentials::{CredentialStore, EncryptedFileStore},
+    rate_limit::RateLimit,
+    retry::RetryPolicy,
+};
 use std::collections::{hash_map::Entry, HashMap};
 
 #[derive(Debug, clap::Parser)]
 struct Args {
-    /// Credentials file. If provided, user credentials will be cached here. If
-    /// the file doesn't exist or credentials are expired, you will be prompted
-    /// for username and password.
-    #[arg(long, default_value = ".something-awful.token")]
+    /// Credentials file. If provided, user credentials will be cached here,
+    /// encrypted with a passphrase that you will be prompted for.
+    #[arg(long, default_value = ".something-awful.credentials")]
+    credentials: Option<String>,
+
+    /// Account to use from the credentials file. Defaults to the only saved
+    /// account. If there is no such account, or its credentials are expired,
+    /// you will be prompted for username and password.
+    #[arg(long)]
+    account: Option<String>,
+
+    /// Deprecated: unencrypted credentials file written by earlier versions.
+    /// If provided, its credentials are used and moved to the credentials
+    /// file.
+    #[arg(long)]
     auth: Option<String>,
 
     /// History file. If provided, seen thread history will be cached here.
@@ -20,11 +36,20 @@ struct Args {
     #[arg(long, default_value_t = 1000)]
     sleep_between_posts_millis: u64,
 
-    /// Time to sleep between polling threads. Set to a higher value if you have
-    /// many favorite threads to poll and want to avoid sending too many
-    /// requests to the server.
+    /// Average time between requests to the server. Set to a higher value if
+    /// you have many favorite threads to poll and want to avoid sending too
+    /// many requests to the server.
     #[arg(long, default_value_t = 1000)]
-    sleep_between_threads_millis: u64,
+    request_interval_millis: u64,
+
+    /// Deprecated: earlier name of --request-interval-millis, which it
+    /// overrides.
+    #[arg(long, hide = true)]
+    sleep_between_threads_millis: Option<u64>,
+
+    /// Number of times to retry requests that fail temporarily.
+    #[arg(long, default_value_t = 3)]
+    max_retries: u32,
 
     /// Time to sleep between refreshing new threads. Don't poll too frequently.
     #[arg(long, default_value_t = 30000)]
@@ -34,29 +59,76 @@ struct Args {
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
     let args = Args::parse();
-    let client = Client::new()?;
+    let mut request_interval_millis = args.request_interval_millis;
+    if let Some(millis) = args.sleep_between_threads_millis {
+        eprintln!(
+            "--sleep-between-threads-millis is deprecated. Use --request-interval-millis instead."
+        );
+        request_interval_millis = millis;
+    }
+    let client = Client::builder()
+        .with_rate_limit(RateLimit::new(
+            1,
+            std::time::Duration::from_millis(request_interval_millis),
+        ))
+        .with_retry_policy(RetryPolicy::new().with_max_retries(args.max_retries))
+        .build()?;
+
+    let mut store = None;
+    if let Some(credentials) = args.credentials.as_ref() {
+        let passphrase = rpassword::prompt_password("Credentials passphrase (hidden): ")?;
+        store = Some(EncryptedFileStore::open(credentials, &passphrase)?);
+    }
 
     let mut logged_in = false;
     if let Some(auth) = args.auth.as_ref() {
+        eprintln!("--auth is deprecated. Use --credentials and --account instead.");
         if let Ok(file) = std::fs::File::open(auth) {
             let reader = std::io::BufReader::new(file);
             if let Ok(()) = client.load_credentials(reader) {
-                if let Ok(Some(_)) = client.fetch_profile(User::CurrentUser).await {
+                if let Ok(Some(profile)) = client.fetch_profile(User::CurrentUser).await {
                     println!("Logged in.");
                     logged_in = true;
+                    if let Some(store) = store.as_ref() {
+                        client.save_account(store, &profile.username, None)?;
+                        println!(
+                            "Moved credentials from {auth} to account {}.",
+                            profile.username
+                        );
+                    }
                 }
             }
         }
     }
 
+    let mut account = args.account.clone();
+    if let (false, Some(store), None) = (logged_in, store.as_ref(), account.as_ref()) {
+        let accounts = store.accounts()?;
+        if let [only] = accounts.as_slice() {
+            account = Some(only.clone());
+        } else if !accounts.is_empty() {
+            println!("Saved accounts: {}", accounts.join(", "));
+            println!("Use --account to choose one.");
+        }
+    }
+
+    if let (false, Some(store), Some(account)) = (logged_in, store.as_ref(), account.as_ref()) {
+        if let Ok(()) = client.switch_account(store, account).await {
+            if let Ok(Some(_)) = client.fetch_profile(User::CurrentUser).await {
+                println!("Logged in.");
+                logged_in = true;
+            }
+        }
+    }
+
     if !logged_in {
         let username = rpassword::prompt_password("Username (hidden): ")?;
         let password = rpassword::prompt_password("Password (hidden): ")?;
         client.login(&username, &password).await?;
 
-        if let Some(auth) = args.auth.as_ref() {
-            let mut file = std::fs::File::create(auth)?;
-            client.save_credentials(&mut file)?;
+        if let Some(store) = store.as_ref() {
+            client.save_account(store, &username, None)?;
+            println!("Saved credentials for account {username}.");
         }
     }
 
@@ -72,8 +144,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
         let threads = client.fetch_bookmarked_threads().await?;
         for thread in threads.into_iter() {
             if thread.unread > 0 {
-                let posts = client.fetch_posts(&thread.id, ThreadPage::New).await?;
-                for post in posts.into_iter() {
+                let page = client.fetch_posts(&thread.id, ThreadPage::New).await?;
+                for post in page.posts.into_iter() {
                     match seen.entry(thread.id.clone()) {
                         Entry::Occupied(mut existing) => {
                             if *existing.get() >= post.index {
@@ -96,7 +168,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
                     println!(r#" > ^ <"#);
                     println!();
                     println!("thread: {}", thread.title);
-                    println!("url: https://forums.somethingawful.com/showthread.php?threadid={}&goto=newpost", thread.id);
+                    println!(
+                        "url: {}showthread.php?threadid={}&goto=newpost",
+                        client.base_url(),
+                        thread.id
+                    );
                     println!("author: {}", post.author_username);
                     println!("time: {}", post.post_date);
                     println!("----------");
@@ -106,10 +182,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error + 'static>> {
                     ))
                     .await;
                 }
-                tokio::time::sleep(std::time::Duration::from_millis(
-                    args.sleep_between_threads_millis,
-                ))
-                .await;
             }
         }
         tokio::time::sleep(std::time::Duration::from_millis(
@@ -1,21 +1,75 @@
 use crate::{
+    credentials::{CredentialStore, Credentials, CredentialsProvider, StoredAccount},
+    edit::{Edit, EditParams},
+    forum_index::ForumIndex,
+    new_thread::{NewThread, NewThreadParams, PollParams, PostIcon},
+    pagination::{Pagination, DEFAULT_PER_PAGE},
+    pm::{Folder, FolderAction, FolderParams, Message, MessageParams, MessageSummary, NewMessage},
     post_list::Post,
-    reply::{Reply, ReplyParams},
-    thread_list::Thread,
+    rate_limit::{RateLimit, TokenBucket},
+    reply::{self, PostedReply, Reply, ReplyParams},
+    retry::RetryPolicy,
+    scrape,
+    search::{self, SearchQuery, SearchResults},
+    thread_list::{BookmarkStar, Thread, ThreadList, ThreadSort},
+    thread_page::ThreadPageView,
+    timestamp,
     Error,
 };
-use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
+use futures::{Stream, StreamExt};
+use reqwest::Method;
+pub use reqwest_cookie_store::{CookieStore, CookieStoreMutex};
 use std::{
     io::{BufRead, Write},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
 };
 use url::Url;
 
-/// Manages access to the Something Awful forums.
+/// The forums URL that clients connect to unless configured otherwise.
+pub const DEFAULT_BASE_URL: &str = "https://forums.somethingawful.com";
+
+/// Manages access to the Something Awful forums. Clones share credentials and
+/// the rate limit.
+#[derive(Clone)]
 pub struct Client {
     base: Url,
     client: reqwest::Client,
     cookie_store: Arc<CookieStoreMutex>,
+    per_page: usize,
+    rate_limiter: Arc<TokenBucket>,
+    retry_policy: RetryPolicy,
+    session: Arc<Session>,
+}
+
+/// Tracks the login session shared between clones of a client.
+struct Session {
+    /// Counts logins, so that a request that failed on an expired session can
+    /// tell whether another request has logged in again since it was sent.
+    logins: AtomicU64,
+
+    /// Held while logging in again, so that concurrent requests on an expired
+    /// session login only once.
+    renewing: tokio::sync::Mutex<()>,
+
+    credentials_provider: Option<CredentialsProvider>,
+}
+
+/// Configures and constructs a Client.
+pub struct ClientBuilder {
+    base: Option<Url>,
+    user_agent: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    cookie_store: Option<Arc<CookieStoreMutex>>,
+    per_page: usize,
+    rate_limit: RateLimit,
+    retry_policy: RetryPolicy,
+    credentials_provider: Option<CredentialsProvider>,
 }
 
 /// References a forum user.
@@ -44,6 +98,9 @@ pub enum ThreadPage {
     /// A specific page of the thread. This should be between 1 and the maximum
     /// page inclusive.
     Page(usize),
+
+    /// The page containing the post with the given ID.
+    Post(String),
 }
 
 /// Contains all data in a user's public profile.
@@ -71,24 +128,183 @@ pub struct Profile {
     pub avpath: String,
 }
 
-impl Client {
-    /// Constructs an unauthenticated client. The user must either login or load
-    /// credentials before using other API functions.
-    pub fn new() -> Result<Client, Error> {
-        let cookie_store = Arc::new(CookieStoreMutex::new(CookieStore::new(None)));
+impl Profile {
+    /// Returns the time that the user registered.
+    pub fn joindate_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        timestamp::from_unix(self.joindate)
+    }
+
+    /// Returns the time of the user's most recent post.
+    pub fn lastpost_utc(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        timestamp::from_unix(self.lastpost)
+    }
+}
+
+impl ClientBuilder {
+    /// Creates a builder with default settings.
+    pub fn new() -> ClientBuilder {
+        ClientBuilder {
+            base: None,
+            user_agent: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            cookie_store: None,
+            per_page: DEFAULT_PER_PAGE,
+            rate_limit: RateLimit::default(),
+            retry_policy: RetryPolicy::default(),
+            credentials_provider: None,
+        }
+    }
+
+    /// Sets the base URL that all requests are resolved against. Defaults to
+    /// DEFAULT_BASE_URL. A base URL with a path must end in a slash, otherwise
+    /// the last path segment is replaced when resolving requests.
+    pub fn with_base_url(mut self, base: Url) -> Self {
+        self.base = Some(base);
+        self
+    }
+
+    /// Sets the User-Agent header sent with every request. Defaults to no
+    /// User-Agent header.
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Sets the total timeout for each request, from connecting until the
+    /// response body is read. Defaults to no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection. Defaults to no
+    /// timeout.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Routes requests through a proxy, overriding any existing proxy.
+    /// Defaults to the system proxy settings.
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Uses an externally owned cookie store, so that credentials can be
+    /// shared with or inspected by the caller. Defaults to a new empty store.
+    pub fn with_cookie_store(mut self, cookie_store: Arc<CookieStoreMutex>) -> Self {
+        self.cookie_store = Some(cookie_store);
+        self
+    }
+
+    /// Sets how many posts or threads are requested for each page of a
+    /// listing, such as a thread or forum. Defaults to DEFAULT_PER_PAGE.
+    pub fn with_per_page(mut self, per_page: usize) -> Self {
+        self.per_page = per_page.max(1);
+        self
+    }
+
+    /// Sets how often requests may be sent. Defaults to RateLimit::default.
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    /// Sets how requests that fail temporarily are retried. Defaults to
+    /// RetryPolicy::default.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Logs in again with credentials from the provider whenever the session
+    /// expires, then resends the failed request. The provider is called at
+    /// most once per expiry. Defaults to reporting SessionExpired instead.
+    pub fn with_credentials_provider<F>(mut self, provider: F) -> Self
+    where
+        F: Fn() -> Option<Credentials> + Send + Sync + 'static,
+    {
+        self.credentials_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Consumes the builder and constructs an unauthenticated client.
+    pub fn build(self) -> Result<Client, Error> {
+        let base = match self.base {
+            Some(base) => base,
+            None => Url::parse(DEFAULT_BASE_URL)?,
+        };
+        let cookie_store = self
+            .cookie_store
+            .unwrap_or_else(|| Arc::new(CookieStoreMutex::new(CookieStore::new(None))));
+
+        let mut builder = reqwest::Client::builder().cookie_provider(cookie_store.clone());
+        if let Some(user_agent) = self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = self.proxy {
+            builder = builder.proxy(proxy);
+        }
+
         Ok(Client {
-            base: Url::parse("https://forums.somethingawful.com")?,
-            client: reqwest::Client::builder()
-                .cookie_provider(cookie_store.clone())
-                .build()?,
+            base,
+            client: builder.build()?,
             cookie_store,
+            per_page: self.per_page,
+            rate_limiter: Arc::new(TokenBucket::new(self.rate_limit)),
+            retry_policy: self.retry_policy,
+            session: Arc::new(Session {
+                logins: AtomicU64::new(0),
+                renewing: tokio::sync::Mutex::new(()),
+                credentials_provider: self.credentials_provider,
+            }),
         })
     }
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        ClientBuilder::new()
+    }
+}
+
+impl Client {
+    /// Constructs an unauthenticated client with default settings. The user
+    /// must either login or load credentials before using other API
+    /// functions.
+    pub fn new() -> Result<Client, Error> {
+        ClientBuilder::new().build()
+    }
+
+    /// Returns a builder for a client with custom settings.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    /// Returns the base URL that requests are resolved against.
+    pub fn base_url(&self) -> &Url {
+        &self.base
+    }
+
+    /// Returns how many posts or threads are requested for each page of a
+    /// listing.
+    pub fn per_page(&self) -> usize {
+        self.per_page
+    }
 
     /// Attempts to login. Returns ReqwestError on a communication error or
     /// LoginError if the login request failed.
     pub async fn login(&self, username: &str, password: &str) -> Result<(), Error> {
-        let response = self
+        let request = self
             .client
             .post(self.base.join("account.php?json=1")?)
             .form(&[
@@ -97,17 +313,20 @@ impl Client {
                 ("password", password),
                 ("next", "/index.php?json=1"),
             ])
-            .send()
-            .await?;
+            .build()?;
 
-        if response.error_for_status().is_err() {
-            Err(Error::LoginError)
-        } else {
-            Ok(())
+        // Logging in must not itself try to renew the session.
+        match self.send_with_retries(request).await {
+            Ok(_) => {
+                self.session.logins.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            Err(Error::ReqwestError(err)) if err.is_status() => Err(Error::LoginError),
+            Err(err) => Err(err),
         }
     }
 
-    /// Returns the profile of a user, or None if that user cannot be found.
+    /// Returns the profile of a user, or None if that user has not registered.
     pub async fn fetch_profile<'a>(&self, user: User<'a>) -> Result<Option<Profile>, Error> {
         let query = match user {
             User::CurrentUser => vec![("action", "getinfo"), ("json", "1")],
@@ -116,49 +335,45 @@ impl Client {
                 vec![("action", "getinfo"), ("username", username), ("json", "1")]
             }
         };
-        let response = self
-            .client
-            .get(self.base.join("member.php")?)
-            .query(&query)
-            .send()
-            .await?;
+        let response = match self
+            .fetch_page(self.client.get(self.base.join("member.php")?).query(&query))
+            .await
+        {
+            Ok(response) => response,
+            Err(Error::UserNotFound) => return Ok(None),
+            Err(err) => return Err(err),
+        };
 
-        // If the username doesn't exist, we get an HTML page that will decode
-        // incorrectly.
-        match response.json().await {
-            Ok(res) => Ok(res),
-            Err(err) => {
-                if err.is_decode() {
-                    Ok(None)
-                } else {
-                    Err(err.into())
-                }
-            }
+        match serde_json::from_str(&response) {
+            Ok(profile) => Ok(Some(profile)),
+            Err(err) => Err(Error::ProfileParsingError(err.to_string())),
         }
     }
 
-    /// Returns all posts on a given page of a thread.
+    /// Returns all posts on a given page of a thread, along with which page
+    /// was returned and metadata about the thread.
     pub async fn fetch_posts(
         &self,
         thread_id: &str,
         index: ThreadPage,
-    ) -> Result<Vec<Post>, Error> {
+    ) -> Result<ThreadPageView, Error> {
+        let per_page = format!("{}", self.per_page);
         let mut _page_string = None;
         let query = match index {
             ThreadPage::First => {
-                vec![("threadid", thread_id), ("perpage", "40")]
+                vec![("threadid", thread_id), ("perpage", &per_page)]
             }
             ThreadPage::Last => {
                 vec![
                     ("threadid", thread_id),
-                    ("perpage", "40"),
+                    ("perpage", &per_page),
                     ("goto", "lastpost"),
                 ]
             }
             ThreadPage::New => {
                 vec![
                     ("threadid", thread_id),
-                    ("perpage", "40"),
+                    ("perpage", &per_page),
                     ("goto", "newpost"),
                 ]
             }
@@ -166,21 +381,62 @@ impl Client {
                 _page_string = Some(format!("{page}"));
                 vec![
                     ("threadid", thread_id),
-                    ("perpage", "40"),
+                    ("perpage", &per_page),
                     ("pagenumber", _page_string.as_ref().unwrap()),
                 ]
             }
+            ThreadPage::Post(ref post_id) => {
+                vec![
+                    ("threadid", thread_id),
+                    ("perpage", &per_page),
+                    ("goto", "post"),
+                    ("postid", post_id.as_str()),
+                ]
+            }
         };
         let response = self
-            .client
-            .get(self.base.join("showthread.php")?)
-            .query(&query)
-            .send()
-            .await?
-            .text()
+            .fetch_page(
+                self.client
+                    .get(self.base.join("showthread.php")?)
+                    .query(&query),
+            )
             .await?;
 
-        Post::parse_list(&response)
+        ThreadPageView::parse(&response)
+    }
+
+    /// Returns every post in a thread, in order, fetching pages lazily as the
+    /// stream is consumed. Starts from the post with the given index, counting
+    /// from 1, or from the first post if start is None. The stream ends after
+    /// the last page, or after the first error.
+    pub fn thread_posts<'a>(
+        &'a self,
+        thread_id: &'a str,
+        start: Option<i64>,
+    ) -> impl Stream<Item = Result<Post, Error>> + 'a {
+        let start = start.unwrap_or(1).max(1);
+        let first_page = Pagination::page_of(start as usize, self.per_page);
+
+        futures::stream::unfold(Some(first_page), move |page| async move {
+            let page = page?;
+            match self.fetch_posts(thread_id, ThreadPage::Page(page)).await {
+                Ok(view) => {
+                    let next_page = view
+                        .pagination
+                        .has_next_page()
+                        .then_some(view.pagination.current_page + 1);
+                    let posts: Vec<_> = view
+                        .posts
+                        .into_iter()
+                        .filter(|post| post.index >= start)
+                        .map(Ok)
+                        .collect();
+                    Some((posts, next_page))
+                }
+                Err(err) => Some((vec![Err(err)], None)),
+            }
+        })
+        .flat_map(futures::stream::iter)
     }
 
     /// Returns metadata about all bookmarked threads.
@@ -189,22 +445,20 @@ impl Client {
         let mut page = 1;
         loop {
             let response = self
-                .client
-                .get(self.base.join("bookmarkthreads.php")?)
-                .query(&[
-                    ("action", "view"),
-                    ("perpage", "40"),
-                    ("pagenumber", &format!("{page}")),
-                ])
-                .send()
-                .await?
-                .text()
+                .fetch_page(
+                    self.client
+                        .get(self.base.join("bookmarkthreads.php")?)
+                        .query(&[
+                            ("action", "view"),
+                            ("perpage", &format!("{}", self.per_page)),
+                            ("pagenumber", &format!("{page}")),
+                        ]),
+                )
                 .await?;
 
-            let mut threads = Thread::parse_list(&response)?;
-            let fetch_next = threads.len() == 40;
-            bookmarked_threads.append(&mut threads);
-            if fetch_next {
+            let mut list = ThreadList::parse(&response)?;
+            bookmarked_threads.append(&mut list.threads);
+            if page < list.pagination.total_pages {
                 page += 1;
             } else {
                 break;
@@ -213,32 +467,544 @@ impl Client {
         Ok(bookmarked_threads)
     }
 
-    /// Posts a reply to a given thread.
-    pub async fn post_reply(&self, thread_id: &str, reply: Reply) -> Result<(), Error> {
+    /// Returns the tree of forums visible to the current user.
+    pub async fn fetch_forum_index(&self) -> Result<ForumIndex, Error> {
         let response = self
-            .client
-            .get(self.base.join("newreply.php")?)
-            .query(&[("action", "newreply"), ("threadid", thread_id)])
-            .send()
-            .await?
-            .text()
+            .fetch_page(self.client.get(self.base.join("index.php")?))
+            .await?;
+
+        ForumIndex::parse(&response)
+    }
+
+    /// Bookmarks a thread.
+    pub async fn add_bookmark(&self, thread_id: &str) -> Result<(), Error> {
+        self.update_bookmark(thread_id, "add").await?;
+        Ok(())
+    }
+
+    /// Removes a thread from bookmarks.
+    pub async fn remove_bookmark(&self, thread_id: &str) -> Result<(), Error> {
+        self.update_bookmark(thread_id, "remove").await?;
+        Ok(())
+    }
+
+    /// Sets the star of a bookmarked thread.
+    pub async fn set_bookmark_star(
+        &self,
+        thread_id: &str,
+        star: BookmarkStar,
+    ) -> Result<(), Error> {
+        // The server only supports cycling to the next star, so cycle until
+        // the requested star is reached.
+        for _ in 0..BookmarkStar::ALL.len() {
+            let response = self.update_bookmark(thread_id, "cat_toggle").await?;
+            let Some(category) = serde_json::from_str::<serde_json::Value>(&response)
+                .ok()
+                .and_then(|x| x.get("category_id")?.as_i64())
+            else {
+                return Err(Error::BookmarkError(response));
+            };
+            if category == star.category() {
+                return Ok(());
+            }
+        }
+        Err(Error::BookmarkError(format!(
+            "star {star:?} was not reached for thread {thread_id}"
+        )))
+    }
+
+    async fn update_bookmark(&self, thread_id: &str, action: &str) -> Result<String, Error> {
+        self.fetch_page(
+            self.client
+                .post(self.base.join("bookmarkthreads.php")?)
+                .form(&[("json", "1"), ("action", action), ("threadid", thread_id)]),
+        )
+        .await
+    }
+
+    /// Marks every post in a thread as unread, so that the thread no longer
+    /// has a last read position.
+    pub async fn mark_thread_unread(&self, thread_id: &str) -> Result<(), Error> {
+        self.fetch_page(self.client.post(self.base.join("showthread.php")?).form(&[
+            ("json", "1"),
+            ("action", "resetseen"),
+            ("threadid", thread_id),
+        ]))
+        .await?;
+        Ok(())
+    }
+
+    /// Sets the last read position of a thread, so that posts up to and
+    /// including the given post index are read and later posts are unread.
+    /// Post indexes start from 1, as in Post::index.
+    pub async fn mark_thread_read_up_to(
+        &self,
+        thread_id: &str,
+        post_index: i64,
+    ) -> Result<(), Error> {
+        self.fetch_page(self.client.get(self.base.join("showthread.php")?).query(&[
+            ("action", "setseen"),
+            ("threadid", thread_id),
+            ("index", &format!("{post_index}")),
+        ]))
+        .await?;
+        Ok(())
+    }
+
+    /// Marks every thread in a forum as read.
+    pub async fn mark_forum_read(&self, forum_id: &str) -> Result<(), Error> {
+        self.fetch_page(
+            self.client
+                .get(self.base.join("forumdisplay.php")?)
+                .query(&[("action", "markread"), ("forumid", forum_id)]),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Returns metadata about the threads on a given page of a forum, sorted
+    /// as requested. The page should be between 1 and the maximum page
+    /// inclusive.
+    pub async fn fetch_forum_threads(
+        &self,
+        forum_id: &str,
+        page: usize,
+        sort: ThreadSort,
+    ) -> Result<ThreadList, Error> {
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("forumdisplay.php")?)
+                    .query(&[
+                        ("forumid", forum_id),
+                        ("perpage", &format!("{}", self.per_page)),
+                        ("pagenumber", &format!("{page}")),
+                    ])
+                    .query(&sort.query()),
+            )
+            .await?;
+
+        ThreadList::parse(&response)
+    }
+
+    /// Searches for posts, returning the first page of results.
+    pub async fn search(&self, query: SearchQuery) -> Result<SearchResults, Error> {
+        let (url, response) = self
+            .fetch_page_with_url(
+                self.client
+                    .post(self.base.join("query.php")?)
+                    .form(&query.into_form()),
+            )
+            .await?;
+
+        // The server redirects to the first page of results, which is keyed by
+        // a query ID.
+        let query_id = scrape::query_param(url.as_str(), "qid");
+        let Some(query_id) = query_id.or_else(|| search::parse_query_id(&response)) else {
+            return Err(Error::SearchParsingError(String::from(
+                "search ID not found",
+            )));
+        };
+        SearchResults::parse(&response, &query_id)
+    }
+
+    /// Returns a given page of results for a previous search. The page should
+    /// be between 1 and the maximum page inclusive.
+    pub async fn fetch_search_results(
+        &self,
+        query_id: &str,
+        page: usize,
+    ) -> Result<SearchResults, Error> {
+        let response = self
+            .fetch_page(self.client.get(self.base.join("query.php")?).query(&[
+                ("action", "results"),
+                ("qid", query_id),
+                ("page", &format!("{page}")),
+            ]))
+            .await?;
+
+        SearchResults::parse(&response, query_id)
+    }
+
+    /// Returns the BBCode that quotes the given posts, in order, as generated
+    /// by the server. Posts may be on different pages of a thread.
+    pub async fn fetch_quote(&self, post_ids: &[&str]) -> Result<String, Error> {
+        let mut quotes = Vec::new();
+        for post_id in post_ids {
+            let response = self
+                .fetch_page(
+                    self.client
+                        .get(self.base.join("newreply.php")?)
+                        .query(&[("action", "newreply"), ("postid", post_id)]),
+                )
+                .await?;
+
+            let params = ReplyParams::parse(&response)?;
+            quotes.push(params.prefilled_message().trim().to_owned());
+        }
+        Ok(quotes.join("\n\n"))
+    }
+
+    /// Posts a reply to a given thread, and returns the new post along with
+    /// where it is in the thread. Returns InvalidBBCode without posting if the
+    /// message fails Reply::validate, or ReplyNotPosted if the server did not
+    /// create the post. Once the server accepts the post, this succeeds even
+    /// if the post cannot be located afterwards.
+    pub async fn post_reply(&self, thread_id: &str, reply: Reply) -> Result<PostedReply, Error> {
+        reply.validate()?;
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("newreply.php")?)
+                    .query(&[("action", "newreply"), ("threadid", thread_id)]),
+            )
             .await?;
 
         let params = ReplyParams::parse(&response)?;
         let form = params.into_form(reply)?;
 
+        let (url, response) = self
+            .fetch_page_with_url(
+                self.client
+                    .post(self.base.join("newreply.php")?)
+                    .multipart(form),
+            )
+            .await?;
+        let reasons = reply::parse_rejection(&response);
+        if !reasons.is_empty() {
+            return Err(Error::ReplyNotPosted(reasons.join("; ")));
+        }
+        let Some(post_id) = reply::parse_posted_id(&url, &response) else {
+            return Err(Error::ReplyNotPosted(String::from("new post not found")));
+        };
+
+        // The reply exists from here on, so failing to locate it must not be
+        // reported as an error, or callers may post it again.
+        let mut posted = PostedReply {
+            post_id,
+            thread_id: thread_id.to_owned(),
+            page: None,
+            index: None,
+        };
+        let Ok(page) = self
+            .fetch_posts(thread_id, ThreadPage::Post(posted.post_id.clone()))
+            .await
+        else {
+            return Ok(posted);
+        };
+        if let Some(post) = page.posts.iter().find(|post| post.id == posted.post_id) {
+            posted.thread_id = page.thread_id;
+            posted.page = Some(page.pagination.current_page);
+            posted.index = Some(post.index);
+        }
+        Ok(posted)
+    }
+
+    /// Returns the BBCode source of a post made by the current user.
+    pub async fn fetch_post_source(&self, post_id: &str) -> Result<String, Error> {
+        let response = self.fetch_edit_form(post_id).await?;
+        Ok(EditParams::parse(&response)?.message)
+    }
+
+    /// Replaces the contents of a post made by the current user.
+    pub async fn edit_post(&self, post_id: &str, edit: Edit) -> Result<(), Error> {
+        let response = self.fetch_edit_form(post_id).await?;
+        let params = EditParams::parse(&response)?;
+        let form = params.into_form(edit)?;
+
+        self.fetch_page(
+            self.client
+                .post(self.base.join("editpost.php")?)
+                .multipart(form),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn fetch_edit_form(&self, post_id: &str) -> Result<String, Error> {
+        self.fetch_page(
+            self.client
+                .get(self.base.join("editpost.php")?)
+                .query(&[("action", "editpost"), ("postid", post_id)]),
+        )
+        .await
+    }
+
+    /// Returns the post icons that can be chosen when starting a thread in a
+    /// given forum.
+    pub async fn fetch_post_icons(&self, forum_id: &str) -> Result<Vec<PostIcon>, Error> {
+        let response = self.fetch_new_thread_form(forum_id).await?;
+        Ok(NewThreadParams::parse(&response)?.icons)
+    }
+
+    /// Starts a new thread in a given forum. Returns InvalidBBCode without
+    /// posting if the message fails NewThread::validate.
+    pub async fn post_new_thread(&self, forum_id: &str, thread: NewThread) -> Result<(), Error> {
+        thread.validate()?;
+        let response = self.fetch_new_thread_form(forum_id).await?;
+        let params = NewThreadParams::parse(&response)?;
+        let (form, poll) = params.into_form(thread)?;
+
         let response = self
-            .client
-            .post(self.base.join("newreply.php")?)
-            .multipart(form)
-            .send()
+            .fetch_page(
+                self.client
+                    .post(self.base.join("newthread.php")?)
+                    .multipart(form),
+            )
             .await?;
 
-        if response.error_for_status().is_err() {
-            Err(Error::LoginError)
-        } else {
-            Ok(())
+        // Threads with polls are created first, and then the server asks for
+        // the poll contents.
+        if let Some(poll) = poll {
+            let params = PollParams::parse(&response)?;
+            self.fetch_page(
+                self.client
+                    .post(self.base.join("poll.php")?)
+                    .form(&params.into_form(poll)),
+            )
+            .await?;
         }
+        Ok(())
+    }
+
+    async fn fetch_new_thread_form(&self, forum_id: &str) -> Result<String, Error> {
+        self.fetch_page(
+            self.client
+                .get(self.base.join("newthread.php")?)
+                .query(&[("action", "newthread"), ("forumid", forum_id)]),
+        )
+        .await
+    }
+
+    /// Returns all private messages within a folder.
+    pub async fn fetch_private_messages(
+        &self,
+        folder: Folder,
+    ) -> Result<Vec<MessageSummary>, Error> {
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("private.php")?)
+                    .query(&[("folderid", folder.id())]),
+            )
+            .await?;
+
+        MessageSummary::parse_list(&response)
+    }
+
+    /// Returns the full contents of a private message. Reading an unread
+    /// message marks it as read.
+    pub async fn fetch_private_message(&self, message_id: &str) -> Result<Message, Error> {
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("private.php")?)
+                    .query(&[("action", "show"), ("privatemessageid", message_id)]),
+            )
+            .await?;
+
+        Message::parse(&response, message_id)
+    }
+
+    /// Sends a new private message.
+    pub async fn send_private_message(&self, message: NewMessage) -> Result<(), Error> {
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("private.php")?)
+                    .query(&[("action", "newmessage")]),
+            )
+            .await?;
+
+        let params = MessageParams::parse(&response)?;
+        self.submit_private_message(params.into_form(message)).await
+    }
+
+    /// Replies to a private message with the given BBCode message. The reply is
+    /// addressed to the original sender with the title chosen by the server.
+    pub async fn reply_private_message(
+        &self,
+        message_id: &str,
+        message: &str,
+    ) -> Result<(), Error> {
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("private.php")?)
+                    .query(&[("action", "newmessage"), ("privatemessageid", message_id)]),
+            )
+            .await?;
+
+        let params = MessageParams::parse(&response)?;
+        let Some(message) = params.prefilled(message) else {
+            return Err(Error::PrivateMessageParsingError(String::from(
+                "reply form is missing recipient or title",
+            )));
+        };
+        self.submit_private_message(params.into_form(message)).await
+    }
+
+    /// Permanently deletes private messages within a folder.
+    pub async fn delete_private_messages(
+        &self,
+        folder: Folder,
+        message_ids: &[&str],
+    ) -> Result<(), Error> {
+        self.manage_private_messages(folder, message_ids, FolderAction::Delete)
+            .await
+    }
+
+    /// Moves private messages from one folder to another.
+    pub async fn move_private_messages(
+        &self,
+        from: Folder,
+        to: Folder,
+        message_ids: &[&str],
+    ) -> Result<(), Error> {
+        self.manage_private_messages(from, message_ids, FolderAction::Move(to))
+            .await
+    }
+
+    async fn submit_private_message(&self, form: Vec<(&'static str, String)>) -> Result<(), Error> {
+        self.fetch_page(self.client.post(self.base.join("private.php")?).form(&form))
+            .await?;
+        Ok(())
+    }
+
+    async fn manage_private_messages(
+        &self,
+        folder: Folder,
+        message_ids: &[&str],
+        action: FolderAction,
+    ) -> Result<(), Error> {
+        let response = self
+            .fetch_page(
+                self.client
+                    .get(self.base.join("private.php")?)
+                    .query(&[("folderid", folder.id())]),
+            )
+            .await?;
+
+        let params = FolderParams::parse(&response)?;
+        self.fetch_page(
+            self.client
+                .post(self.base.join("private.php")?)
+                .form(&params.into_form(message_ids, action)),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Sends a request for a page and returns its contents. Returns the error
+    /// described by the page if the forums responded with one of their
+    /// standard error pages.
+    async fn fetch_page(&self, request: reqwest::RequestBuilder) -> Result<String, Error> {
+        let (_, page) = self.fetch_page_with_url(request).await?;
+        Ok(page)
+    }
+
+    /// Like fetch_page, but also returns the URL of the page after following
+    /// redirects. All requests are sent through here, so that they are rate
+    /// limited, retried, and resent after logging in again if the session
+    /// expired.
+    async fn fetch_page_with_url(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<(Url, String), Error> {
+        let request = request.build()?;
+        let logins = self.session.logins.load(Ordering::SeqCst);
+        let had_session = self.has_session();
+        let replay = request.try_clone();
+
+        let err = match self.send_with_retries(request).await {
+            Ok(page) => return Ok(page),
+            Err(Error::NotLoggedIn) if had_session => Error::SessionExpired,
+            Err(err) => return Err(err),
+        };
+        let Some(replay) = replay else {
+            return Err(err);
+        };
+        if !self.renew_session(logins).await? {
+            return Err(err);
+        }
+        match self.send_with_retries(replay).await {
+            Err(Error::NotLoggedIn) => Err(Error::SessionExpired),
+            result => result,
+        }
+    }
+
+    /// Logs in again with credentials from the provider, unless another
+    /// request already did since the given login count was read. Returns
+    /// whether there is a new session to resend requests with.
+    async fn renew_session(&self, logins: u64) -> Result<bool, Error> {
+        let Some(provider) = self.session.credentials_provider.as_ref() else {
+            return Ok(false);
+        };
+        let _renewing = self.session.renewing.lock().await;
+        if self.session.logins.load(Ordering::SeqCst) != logins {
+            return Ok(true);
+        }
+        let Some(credentials) = provider() else {
+            return Ok(false);
+        };
+        self.login(&credentials.username, &credentials.password)
+            .await?;
+        Ok(true)
+    }
+
+    /// Returns whether the client holds session cookies for the forums, such
+    /// as after logging in or loading credentials.
+    fn has_session(&self) -> bool {
+        let store = self.cookie_store.lock().expect("BUG: lock failed");
+        store
+            .matches(&self.base)
+            .iter()
+            .any(|cookie| cookie.name() == "bbuserid")
+    }
+
+    /// Sends a request, retrying it according to the retry policy.
+    async fn send_with_retries(
+        &self,
+        mut request: reqwest::Request,
+    ) -> Result<(Url, String), Error> {
+        let idempotent = matches!(*request.method(), Method::GET | Method::HEAD);
+        let mut attempts = 0;
+        loop {
+            // Requests with streamed bodies, such as attachments, cannot be
+            // sent twice.
+            let retry = request.try_clone();
+            self.rate_limiter.acquire().await;
+            attempts += 1;
+            let err = match self.send_page(request).await {
+                Ok(page) => return Ok(page),
+                Err(err) => err,
+            };
+            let Some(retry) = retry else {
+                return Err(err);
+            };
+            let Some(backoff) = self.retry_policy.backoff(attempts, idempotent, &err) else {
+                return Err(err);
+            };
+            tokio::time::sleep(backoff).await;
+            request = retry;
+        }
+    }
+
+    /// Sends a request once, checking the response for an error page.
+    async fn send_page(&self, request: reqwest::Request) -> Result<(Url, String), Error> {
+        let response = self.client.execute(request).await?;
+        let url = response.url().clone();
+        let status = response.error_for_status_ref().err();
+        let page = response.text().await?;
+
+        // Error pages are not always served with an error status.
+        if let Some(err) = Error::from_error_page(&page) {
+            return Err(err);
+        }
+        if let Some(err) = status {
+            return Err(err.into());
+        }
+        Ok((url, page))
     }
 
     /// Saves credentials to JSON. The user must be logged in for the
@@ -257,6 +1023,48 @@ impl Client {
         for cookie in loaded.iter_unexpired() {
             store.insert(cookie.clone(), &self.base)?;
         }
+        self.session.logins.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Saves the current session in a credential store under a username,
+    /// along with the password if given, so that the account can be switched
+    /// back to later.
+    pub fn save_account(
+        &self,
+        store: &dyn CredentialStore,
+        username: &str,
+        password: Option<&str>,
+    ) -> Result<(), Error> {
+        let mut session = Vec::new();
+        self.save_credentials(&mut session)?;
+        store.save(&StoredAccount {
+            username: username.to_owned(),
+            session: String::from_utf8(session).expect("BUG: cookie JSON is not UTF-8"),
+            password: password.map(str::to_owned),
+        })
+    }
+
+    /// Replaces the current session with the one saved for an account in a
+    /// credential store. Clones of the client switch too. Logs in again if
+    /// the saved session has expired and its password was saved. Returns
+    /// AccountNotFound if no account is saved under the username.
+    pub async fn switch_account(
+        &self,
+        store: &dyn CredentialStore,
+        username: &str,
+    ) -> Result<(), Error> {
+        let Some(account) = store.load(username)? else {
+            return Err(Error::AccountNotFound(username.to_owned()));
+        };
+        self.cookie_store.lock().expect("BUG: lock failed").clear();
+        self.load_credentials(account.session.as_bytes())?;
+        if !self.has_session() {
+            if let Some(credentials) = account.credentials() {
+                self.login(&credentials.username, &credentials.password)
+                    .await?;
+            }
+        }
         Ok(())
     }
 }
@@ -0,0 +1,303 @@
+use crate::Error;
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A username and password used to login.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+impl Credentials {
+    /// Creates credentials for an account.
+    pub fn new(username: &str, password: &str) -> Credentials {
+        Credentials {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Credentials {
+    // Keep passwords out of logs.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("username", &self.username)
+            .field("password", &"<hidden>")
+            .finish()
+    }
+}
+
+/// Supplies credentials to login again with when a session expires, or None
+/// to give up and report the expired session.
+pub type CredentialsProvider = Arc<dyn Fn() -> Option<Credentials> + Send + Sync>;
+
+/// A login session saved for an account.
+#[derive(Clone)]
+pub struct StoredAccount {
+    pub username: String,
+
+    /// Session cookies, as written by Client::save_credentials.
+    pub session: String,
+
+    /// The account's password, if saved, so that the account can login again
+    /// once the session expires.
+    pub password: Option<String>,
+}
+
+impl StoredAccount {
+    /// Returns the credentials to login with, if the password was saved.
+    pub fn credentials(&self) -> Option<Credentials> {
+        let password = self.password.as_ref()?;
+        Some(Credentials::new(&self.username, password))
+    }
+}
+
+impl std::fmt::Debug for StoredAccount {
+    // Keep sessions and passwords out of logs.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredAccount")
+            .field("username", &self.username)
+            .field("session", &"<hidden>")
+            .field("password", &self.password.as_ref().map(|_| "<hidden>"))
+            .finish()
+    }
+}
+
+/// Saves login sessions for any number of accounts, keyed by username.
+pub trait CredentialStore: Send + Sync {
+    /// Returns the usernames of all saved accounts in sorted order.
+    fn accounts(&self) -> Result<Vec<String>, Error>;
+
+    /// Returns the account saved under a username, or None if there is none.
+    fn load(&self, username: &str) -> Result<Option<StoredAccount>, Error>;
+
+    /// Saves an account, replacing any account saved under the same username.
+    fn save(&self, account: &StoredAccount) -> Result<(), Error>;
+
+    /// Removes an account. Returns whether the account was saved.
+    fn remove(&self, username: &str) -> Result<bool, Error>;
+}
+
+/// The version of the encrypted file format.
+const FILE_VERSION: u32 = 1;
+
+/// Encrypted with the file's key to check the passphrase when opening it.
+const PASSPHRASE_CHECK: &[u8] = b"something-awful";
+
+/// Stores accounts in a file encrypted with a passphrase. Usernames are
+/// stored in the clear so that accounts can be listed, but sessions and
+/// passwords are encrypted with ChaCha20-Poly1305, using a key derived from
+/// the passphrase with Argon2id.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+    contents: Mutex<StoreFile>,
+}
+
+/// The contents of an encrypted file.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoreFile {
+    version: u32,
+    salt: String,
+    check: Sealed,
+    accounts: BTreeMap<String, Sealed>,
+}
+
+/// Data encrypted with the file's key.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Sealed {
+    nonce: String,
+    ciphertext: String,
+}
+
+/// The encrypted part of an account.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Secrets {
+    session: String,
+    password: Option<String>,
+}
+
+impl EncryptedFileStore {
+    /// Opens the store at a path, or starts an empty one if the file does not
+    /// exist yet. The file is only written once an account is saved. Returns
+    /// WrongPassphrase if the file was encrypted with a different passphrase.
+    pub fn open<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<EncryptedFileStore, Error> {
+        let path = path.as_ref().to_owned();
+        let existing = match std::fs::read(&path) {
+            Ok(existing) => existing,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return EncryptedFileStore::create(path, passphrase);
+            }
+            Err(err) => return Err(Error::CredentialStoreIOError(err)),
+        };
+
+        let contents: StoreFile = serde_json::from_slice(&existing)
+            .map_err(|err| Error::CredentialStoreError(err.to_string()))?;
+        if contents.version != FILE_VERSION {
+            return Err(Error::CredentialStoreError(format!(
+                "unsupported version {}",
+                contents.version
+            )));
+        }
+        let salt = decode(&contents.salt)?;
+        let cipher = derive_cipher(passphrase, &salt)?;
+        if unseal(&cipher, &contents.check, b"").as_deref() != Some(PASSPHRASE_CHECK) {
+            return Err(Error::WrongPassphrase);
+        }
+
+        Ok(EncryptedFileStore {
+            path,
+            cipher,
+            contents: Mutex::new(contents),
+        })
+    }
+
+    /// Starts an empty store with a new salt.
+    fn create(path: PathBuf, passphrase: &str) -> Result<EncryptedFileStore, Error> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let cipher = derive_cipher(passphrase, &salt)?;
+        let check = seal(&cipher, PASSPHRASE_CHECK, b"")?;
+        Ok(EncryptedFileStore {
+            path,
+            cipher,
+            contents: Mutex::new(StoreFile {
+                version: FILE_VERSION,
+                salt: BASE64.encode(salt),
+                check,
+                accounts: BTreeMap::new(),
+            }),
+        })
+    }
+
+    /// Replaces the file with the given contents. The file is only readable
+    /// by its owner, and is replaced in one step so that it is never left
+    /// partially written.
+    fn write(&self, contents: &StoreFile) -> Result<(), Error> {
+        let serialized = serde_json::to_vec_pretty(contents)
+            .map_err(|err| Error::CredentialStoreError(err.to_string()))?;
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let write = || -> std::io::Result<()> {
+            let mut file = options.open(&temp)?;
+            file.write_all(&serialized)?;
+            file.sync_all()?;
+            std::fs::rename(&temp, &self.path)
+        };
+        write().map_err(Error::CredentialStoreIOError)
+    }
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn accounts(&self) -> Result<Vec<String>, Error> {
+        let contents = self.contents.lock().expect("BUG: lock failed");
+        Ok(contents.accounts.keys().cloned().collect())
+    }
+
+    fn load(&self, username: &str) -> Result<Option<StoredAccount>, Error> {
+        let contents = self.contents.lock().expect("BUG: lock failed");
+        let Some(sealed) = contents.accounts.get(username) else {
+            return Ok(None);
+        };
+        // The username is authenticated along with the secrets, so that they
+        // cannot be moved to another account.
+        let Some(secrets) = unseal(&self.cipher, sealed, username.as_bytes()) else {
+            return Err(Error::CredentialStoreError(format!(
+                "unable to decrypt account {username}"
+            )));
+        };
+        let secrets: Secrets = serde_json::from_slice(&secrets)
+            .map_err(|err| Error::CredentialStoreError(err.to_string()))?;
+        Ok(Some(StoredAccount {
+            username: username.to_owned(),
+            session: secrets.session,
+            password: secrets.password,
+        }))
+    }
+
+    fn save(&self, account: &StoredAccount) -> Result<(), Error> {
+        let secrets = serde_json::to_vec(&Secrets {
+            session: account.session.clone(),
+            password: account.password.clone(),
+        })
+        .map_err(|err| Error::CredentialStoreError(err.to_string()))?;
+        let sealed = seal(&self.cipher, &secrets, account.username.as_bytes())?;
+
+        let mut contents = self.contents.lock().expect("BUG: lock failed");
+        contents.accounts.insert(account.username.clone(), sealed);
+        self.write(&contents)
+    }
+
+    fn remove(&self, username: &str) -> Result<bool, Error> {
+        let mut contents = self.contents.lock().expect("BUG: lock failed");
+        if contents.accounts.remove(username).is_none() {
+            return Ok(false);
+        }
+        self.write(&contents)?;
+        Ok(true)
+    }
+}
+
+/// Derives the file's key from a passphrase.
+fn derive_cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, Error> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|err| Error::CredentialStoreError(err.to_string()))?;
+    Ok(ChaCha20Poly1305::new(&key))
+}
+
+/// Encrypts data under a new random nonce.
+fn seal(cipher: &ChaCha20Poly1305, data: &[u8], aad: &[u8]) -> Result<Sealed, Error> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: data, aad })
+        .map_err(|err| Error::CredentialStoreError(err.to_string()))?;
+    Ok(Sealed {
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+/// Decrypts data, or returns None if it was encrypted with a different key or
+/// has been tampered with.
+fn unseal(cipher: &ChaCha20Poly1305, sealed: &Sealed, aad: &[u8]) -> Option<Vec<u8>> {
+    let nonce = BASE64.decode(&sealed.nonce).ok()?;
+    if nonce.len() != 12 {
+        return None;
+    }
+    let ciphertext = BASE64.decode(&sealed.ciphertext).ok()?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad,
+            },
+        )
+        .ok()
+}
+
+/// Decodes base64 from the file.
+fn decode(encoded: &str) -> Result<Vec<u8>, Error> {
+    BASE64
+        .decode(encoded)
+        .map_err(|err| Error::CredentialStoreError(err.to_string()))
+}
@@ -0,0 +1,107 @@
+use crate::{scrape, Error};
+
+/// Edit parameters read from the server.
+pub struct EditParams {
+    action: String,
+    postid: String,
+    formkey: String,
+    form_cookie: String,
+
+    /// The current BBCode source of the post.
+    pub message: String,
+}
+
+impl EditParams {
+    /// Prepares an edit by parsing an edit form for the current post source
+    /// and metadata that must be sent back in the edit request.
+    pub fn parse(document: &str) -> Result<EditParams, Error> {
+        let document = scraper::Html::parse_document(document);
+        let Some(form) = scrape::form(&document, "vbform") else {
+            return Err(Error::EditParsingError);
+        };
+        let Some(action) = scrape::input_value(&form, "action") else {
+            return Err(Error::EditParsingError);
+        };
+        let Some(postid) = scrape::input_value(&form, "postid") else {
+            return Err(Error::EditParsingError);
+        };
+        let Some(formkey) = scrape::input_value(&form, "formkey") else {
+            return Err(Error::EditParsingError);
+        };
+        let Some(form_cookie) = scrape::input_value(&form, "form_cookie") else {
+            return Err(Error::EditParsingError);
+        };
+
+        let selector =
+            scraper::Selector::parse(r#"textarea[name="message"]"#).expect("BUG: illegal selector");
+        let Some(message) = form.select(&selector).next() else {
+            return Err(Error::EditParsingError);
+        };
+        let message = message.text().collect();
+
+        Ok(EditParams {
+            action,
+            postid,
+            formkey,
+            form_cookie,
+            message,
+        })
+    }
+
+    /// Consumes the builder and an edit, returning a form corresponding to the
+    /// edit payload.
+    pub fn into_form(self, edit: Edit) -> Result<reqwest::multipart::Form, Error> {
+        let mut form = reqwest::multipart::Form::new()
+            .text("action", self.action)
+            .text("postid", self.postid)
+            .text("formkey", self.formkey)
+            .text("form_cookie", self.form_cookie)
+            .text("message", edit.message)
+            .text("parseurl", "yes")
+            .text("submit", "Save Changes");
+        if let Some(reason) = edit.reason {
+            form = form.text("editreason", reason);
+        }
+        // Uploading an attachment replaces any existing attachment.
+        if let Some((attachment_filename, attachment_contents)) = edit.attachment {
+            form = form.part(
+                "attachment",
+                reqwest::multipart::Part::bytes(attachment_contents)
+                    .file_name(attachment_filename)
+                    .mime_str("application/octet-stream")?,
+            );
+        }
+        Ok(form)
+    }
+}
+
+/// Represents new contents for a previously made post.
+pub struct Edit {
+    message: String,
+    reason: Option<String>,
+    attachment: Option<(String, Vec<u8>)>,
+}
+
+impl Edit {
+    /// Creates an edit that replaces the post with the given BBCode message.
+    pub fn new(message: &str) -> Edit {
+        Edit {
+            message: message.to_owned(),
+            reason: None,
+            attachment: None,
+        }
+    }
+
+    /// Sets the reason shown alongside the edit notice. Defaults to no reason.
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+
+    /// Replaces the post attachment. Defaults to keeping the existing
+    /// attachment, if any.
+    pub fn with_attachment(mut self, filename: &str, contents: &[u8]) -> Self {
+        self.attachment = Some((filename.to_owned(), contents.to_owned()));
+        self
+    }
+}
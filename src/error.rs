@@ -1,3 +1,6 @@
+use crate::timestamp::Timestamp;
+use std::time::Duration;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("reqwest error: {0}")]
@@ -21,6 +24,133 @@ pub enum Error {
     #[error("unable to parse post: {0}")]
     PostParsingError(String),
 
+    #[error("unable to parse thread page: {0}")]
+    ThreadPageParsingError(String),
+
+    #[error("unable to parse forum index: {0}")]
+    ForumIndexParsingError(String),
+
+    #[error("unable to parse private message: {0}")]
+    PrivateMessageParsingError(String),
+
     #[error("unable to parse reply")]
     ReplyParsingError,
+
+    #[error("reply was not posted: {0}")]
+    ReplyNotPosted(String),
+
+    #[error("invalid BBCode: {}", .0.iter().map(|x| x.to_string()).collect::<Vec<_>>().join("; "))]
+    InvalidBBCode(Vec<crate::bbcode::ValidationIssue>),
+
+    #[error("unable to parse new thread form")]
+    NewThreadParsingError,
+
+    #[error("post icon {0} is not available in this forum")]
+    InvalidPostIcon(String),
+
+    #[error("invalid poll: {0}")]
+    InvalidPoll(String),
+
+    #[error("unable to parse edit form")]
+    EditParsingError,
+
+    #[error("unable to parse search results: {0}")]
+    SearchParsingError(String),
+
+    #[error("unable to update bookmark: {0}")]
+    BookmarkError(String),
+
+    #[error("unable to parse profile: {0}")]
+    ProfileParsingError(String),
+
+    #[error("not logged in")]
+    NotLoggedIn,
+
+    #[error("session expired, login again")]
+    SessionExpired,
+
+    #[error("credential store error: {0}")]
+    CredentialStoreError(String),
+
+    #[error("credential store IO error: {0}")]
+    CredentialStoreIOError(std::io::Error),
+
+    #[error("wrong passphrase for credential store")]
+    WrongPassphrase,
+
+    #[error("no saved account for {0}")]
+    AccountNotFound(String),
+
+    #[error("no permission to access this page")]
+    NoPermission,
+
+    #[error("user not found")]
+    UserNotFound,
+
+    #[error("thread not found")]
+    ThreadNotFound,
+
+    #[error("thread is closed")]
+    ThreadClosed,
+
+    #[error("on probation{}", .until.as_ref().map(|x| format!(" until {x}")).unwrap_or_default())]
+    Probation { until: Option<Timestamp> },
+
+    #[error("flood control{}", .wait.map(|x| format!(", try again in {} seconds", x.as_secs())).unwrap_or_default())]
+    FloodControl { wait: Option<Duration> },
+
+    #[error("forum database error")]
+    DatabaseError,
+}
+
+impl Error {
+    /// Returns the error described by one of the forums' standard error pages,
+    /// or None if the document is not a recognized error page.
+    pub fn from_error_page(document: &str) -> Option<Error> {
+        // Avoid parsing pages that cannot be error pages.
+        if !document.contains("standard") {
+            return None;
+        }
+        let document = scraper::Html::parse_document(document);
+        let selector = scraper::Selector::parse(r#".standarderror, div.standard"#)
+            .expect("BUG: illegal selector");
+        let message = document.select(&selector).next()?;
+        let message = message.text().collect::<Vec<_>>().join(" ");
+        let message = message.split_whitespace().collect::<Vec<_>>().join(" ");
+        let lowercase = message.to_lowercase();
+
+        let error = if lowercase.contains("must be a registered forums member") {
+            Error::NotLoggedIn
+        } else if lowercase.contains("do not have permission")
+            || lowercase.contains("don't have permission")
+        {
+            Error::NoPermission
+        } else if lowercase.contains("has not registered") {
+            Error::UserNotFound
+        } else if lowercase.contains("specified thread was not found")
+            || lowercase.contains("invalid thread specified")
+        {
+            Error::ThreadNotFound
+        } else if lowercase.contains("this thread is closed") {
+            Error::ThreadClosed
+        } else if lowercase.contains("probation") {
+            let until = message
+                .find("probation until ")
+                .map(|start| &message[start + "probation until ".len()..])
+                .map(|until| until.split_once(". ").map_or(until, |(until, _)| until))
+                .map(|until| Timestamp::parse(until.trim_end_matches('.')));
+            Error::Probation { until }
+        } else if lowercase.contains("flood control") {
+            let wait = lowercase
+                .split_once("wait ")
+                .and_then(|(_, wait)| wait.split_whitespace().next()?.parse().ok())
+                .map(Duration::from_secs);
+            Error::FloodControl { wait }
+        } else if lowercase.contains("problem with the something awful forums database") {
+            Error::DatabaseError
+        } else {
+            return None;
+        };
+        Some(error)
+    }
 }
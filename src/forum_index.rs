@@ -0,0 +1,183 @@
+use crate::{scrape, Error};
+
+/// Represents the tree of all forums visible to the current user.
+#[derive(Debug)]
+pub struct ForumIndex {
+    pub categories: Vec<ForumCategory>,
+}
+
+/// Represents a top-level grouping of forums, such as "Main".
+#[derive(Debug)]
+pub struct ForumCategory {
+    pub id: String,
+    pub title: String,
+    pub forums: Vec<Forum>,
+}
+
+/// Represents a single forum.
+#[derive(Debug)]
+pub struct Forum {
+    pub id: String,
+    pub title: String,
+
+    /// Empty if the forum has no description. Subforums are listed without
+    /// descriptions on the index.
+    pub description: String,
+
+    /// Empty if the forum has no moderators. Subforums are listed without
+    /// moderators on the index.
+    pub moderators: Vec<Moderator>,
+
+    pub subforums: Vec<Forum>,
+}
+
+/// Represents a moderator of a forum.
+#[derive(Debug)]
+pub struct Moderator {
+    pub user_id: String,
+    pub username: String,
+}
+
+impl ForumIndex {
+    /// Parses the forum index page.
+    pub fn parse(document: &str) -> Result<ForumIndex, Error> {
+        let document = scraper::Html::parse_document(document);
+        let selector =
+            scraper::Selector::parse(r#"table#forums tr"#).expect("BUG: illegal selector");
+
+        let mut categories: Vec<ForumCategory> = Vec::new();
+        for row in document.select(&selector) {
+            let classes: Vec<_> = row.value().classes().collect();
+            if classes.contains(&"section") {
+                let selector =
+                    scraper::Selector::parse(r#"th.category a"#).expect("BUG: illegal selector");
+                let Some(link) = row.select(&selector).next() else {
+                    return Err(Error::ForumIndexParsingError(String::from(
+                        "missing category link",
+                    )));
+                };
+                let Some(id) = link
+                    .value()
+                    .attr("href")
+                    .and_then(|href| scrape::query_param(href, "forumid"))
+                else {
+                    return Err(Error::ForumIndexParsingError(String::from(
+                        "missing category ID",
+                    )));
+                };
+                categories.push(ForumCategory {
+                    id,
+                    title: scrape::trimmed_text(&link),
+                    forums: Vec::new(),
+                });
+            } else if classes.contains(&"forum") {
+                let Some(category) = categories.last_mut() else {
+                    return Err(Error::ForumIndexParsingError(String::from(
+                        "forum outside of a category",
+                    )));
+                };
+                let Some(forum) = Forum::parse_row(row) else {
+                    return Err(Error::ForumIndexParsingError(String::from(
+                        "malformed forum row",
+                    )));
+                };
+                category.forums.push(forum);
+            }
+        }
+
+        if categories.is_empty() {
+            return Err(Error::ForumIndexParsingError(String::from(
+                "no forum categories found",
+            )));
+        }
+        Ok(ForumIndex { categories })
+    }
+
+    /// Returns all forums and subforums in the index, depth first.
+    pub fn forums(&self) -> Vec<&Forum> {
+        fn visit<'a>(forum: &'a Forum, out: &mut Vec<&'a Forum>) {
+            out.push(forum);
+            for subforum in forum.subforums.iter() {
+                visit(subforum, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        for category in self.categories.iter() {
+            for forum in category.forums.iter() {
+                visit(forum, &mut out);
+            }
+        }
+        out
+    }
+}
+
+impl Forum {
+    /// Parses a forum row on the index, returning None if the row is
+    /// malformed.
+    fn parse_row(row: scraper::ElementRef) -> Option<Forum> {
+        let selector =
+            scraper::Selector::parse(r#"td.title a.forum"#).expect("BUG: illegal selector");
+        let link = row.select(&selector).next()?;
+        let id = scrape::query_param(link.value().attr("href")?, "forumid")?;
+        let title = scrape::trimmed_text(&link);
+
+        let selector =
+            scraper::Selector::parse(r#"td.title span.forumdesc"#).expect("BUG: illegal selector");
+        let description = row
+            .select(&selector)
+            .next()
+            .map(|description| {
+                scrape::trimmed_text(&description)
+                    .trim_start_matches('-')
+                    .trim_start()
+                    .to_owned()
+            })
+            .unwrap_or_default();
+
+        let selector =
+            scraper::Selector::parse(r#"td.title div.subforums a"#).expect("BUG: illegal selector");
+        let mut subforums = Vec::new();
+        for link in row.select(&selector) {
+            let Some(id) = link
+                .value()
+                .attr("href")
+                .and_then(|href| scrape::query_param(href, "forumid"))
+            else {
+                continue;
+            };
+            subforums.push(Forum {
+                id,
+                title: scrape::trimmed_text(&link),
+                description: String::new(),
+                moderators: Vec::new(),
+                subforums: Vec::new(),
+            });
+        }
+
+        let selector =
+            scraper::Selector::parse(r#"td.moderators a"#).expect("BUG: illegal selector");
+        let mut moderators = Vec::new();
+        for link in row.select(&selector) {
+            let Some(user_id) = link
+                .value()
+                .attr("href")
+                .and_then(|href| scrape::query_param(href, "userid"))
+            else {
+                continue;
+            };
+            moderators.push(Moderator {
+                user_id,
+                username: scrape::trimmed_text(&link),
+            });
+        }
+
+        Some(Forum {
+            id,
+            title,
+            description,
+            moderators,
+            subforums,
+        })
+    }
+}
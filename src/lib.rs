@@ -1,6 +1,22 @@
+pub mod bbcode;
 pub mod client;
+pub mod credentials;
+pub mod edit;
 pub mod error;
 pub use error::*;
+pub mod forum_index;
+#[cfg(feature = "mock-server")]
+pub mod mock;
+pub mod new_thread;
+pub mod pagination;
+pub mod pm;
+pub mod post_body;
 pub mod post_list;
+pub mod rate_limit;
 pub mod reply;
+pub mod retry;
+mod scrape;
+pub mod search;
 pub mod thread_list;
+pub mod thread_page;
+pub mod timestamp;
@@ -0,0 +1,731 @@
+//! A local server that emulates a small part of the forums, so that clients
+//! can be tested end to end without network access. Requires the mock-server
+//! feature.
+//!
+//! The server accepts a single account, USERNAME with PASSWORD, and hosts a
+//! single thread, THREAD_ID, with two pages of POSTS_PER_PAGE posts. Pages are
+//! served from the captured pages in fixtures, while replies, edits, new
+//! threads, polls, bookmark changes and read marks are recorded so that tests
+//! can inspect them. Edits and read marks are recorded without changing the
+//! pages.
+//! Replies are shown at the end of the last page. New threads may be started
+//! in FORUM_ID.
+
+use crate::{
+    client::{Client, ClientBuilder},
+    rate_limit::RateLimit,
+    retry::RetryPolicy,
+    thread_list::BookmarkStar,
+};
+use hyper::{
+    header,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use url::Url;
+
+/// Pages captured from the forums, as served by the mock server. These can
+/// also be passed directly to the parsers.
+pub mod fixtures {
+    /// The first page of the mock thread.
+    pub const THREAD_PAGE_1: &str = include_str!("../fixtures/showthread_1.html");
+
+    /// The second and last page of the mock thread.
+    pub const THREAD_PAGE_2: &str = include_str!("../fixtures/showthread_2.html");
+
+    /// The index of all forums.
+    pub const FORUM_INDEX: &str = include_str!("../fixtures/index.html");
+
+    /// The first of two pages of results for a search.
+    pub const SEARCH_RESULTS: &str = include_str!("../fixtures/query.html");
+
+    /// The list of bookmarked threads.
+    pub const BOOKMARKS: &str = include_str!("../fixtures/bookmarkthreads.html");
+
+    /// The form for replying to the mock thread.
+    pub const NEW_REPLY: &str = include_str!("../fixtures/newreply.html");
+
+    /// The reply form prefilled with a quote of the second post in the mock
+    /// thread.
+    pub const NEW_REPLY_QUOTE: &str = include_str!("../fixtures/newreply_quote.html");
+
+    /// The form for editing the editable post.
+    pub const EDIT_POST: &str = include_str!("../fixtures/editpost.html");
+
+    /// The form for starting a thread in the mock forum.
+    pub const NEW_THREAD: &str = include_str!("../fixtures/newthread.html");
+
+    /// The form for adding a poll to a thread just started in the mock forum.
+    pub const NEW_POLL: &str = include_str!("../fixtures/poll.html");
+
+    /// The first page of the private message inbox. Not served by the mock
+    /// server.
+    pub const PRIVATE_MESSAGES: &str = include_str!("../fixtures/private_folder.html");
+
+    /// A received private message. Not served by the mock server.
+    pub const PRIVATE_MESSAGE: &str = include_str!("../fixtures/private_message.html");
+
+    /// The form for replying to the received private message. Not served by
+    /// the mock server.
+    pub const NEW_PRIVATE_MESSAGE: &str = include_str!("../fixtures/private_newmessage.html");
+
+    /// The JSON profile of the mock account.
+    pub const PROFILE: &str = include_str!("../fixtures/member.json");
+
+    /// The page shown instead of members-only pages when logged out.
+    pub const NOT_LOGGED_IN: &str = include_str!("../fixtures/not_logged_in.html");
+
+    /// The page shown instead of the profile of a user that does not exist.
+    pub const USER_NOT_FOUND: &str = include_str!("../fixtures/user_not_found.html");
+
+    /// The page shown for pages that the account may not access.
+    pub const NO_PERMISSION: &str = include_str!("../fixtures/no_permission.html");
+
+    /// The page shown for threads that do not exist.
+    pub const THREAD_NOT_FOUND: &str = include_str!("../fixtures/thread_not_found.html");
+
+    /// The page shown when replying to a closed thread.
+    pub const THREAD_CLOSED: &str = include_str!("../fixtures/thread_closed.html");
+
+    /// The page shown when posting while on probation.
+    pub const PROBATION: &str = include_str!("../fixtures/probation.html");
+
+    /// The page shown when posting too soon after the previous post.
+    pub const FLOOD_CONTROL: &str = include_str!("../fixtures/flood_control.html");
+
+    /// The page shown when the forums cannot reach their database.
+    pub const DATABASE_ERROR: &str = include_str!("../fixtures/database_error.html");
+}
+
+/// The username of the only account.
+pub const USERNAME: &str = "MockUser";
+
+/// The password of the only account.
+pub const PASSWORD: &str = "hunter2";
+
+/// The user ID of the only account.
+pub const USER_ID: &str = "1234";
+
+/// The ID of the only thread.
+pub const THREAD_ID: &str = "3900000";
+
+/// The ID of a closed thread, which is listed in bookmarks but cannot be
+/// viewed or replied to.
+pub const CLOSED_THREAD_ID: &str = "3900001";
+
+/// The number of posts on each page of the thread, regardless of the page
+/// size that the client requests.
+pub const POSTS_PER_PAGE: usize = 2;
+
+/// The ID of every search, whose results are the search results fixture.
+pub const QUERY_ID: &str = "8800001";
+
+/// The ID of the only post whose edit form is served. It was posted by the
+/// account on the second page of the thread.
+pub const EDITABLE_POST_ID: &str = "500000003";
+
+/// The ID of the only forum that accepts new threads.
+pub const FORUM_ID: &str = "1";
+
+/// The ID given to every new thread, which is not served.
+pub const NEW_THREAD_ID: &str = "3900002";
+
+/// The form key embedded in the reply form, which replies must send back.
+const FORM_KEY: &str = "0123456789abcdef0123456789abcdef";
+
+/// The ID of the first post on the second page of the thread fixtures.
+const SECOND_PAGE_POST_ID: u64 = 500000003;
+
+/// The ID of the last post in the thread fixtures.
+const LAST_POST_ID: u64 = 500000004;
+
+/// The number of posts in the thread fixtures.
+const FIXTURE_POSTS: u64 = 4;
+
+/// The author and BBCode source of each post in the thread fixtures, which
+/// are quoted by the reply form.
+const POST_SOURCES: [(&str, &str, &str); 4] = [
+    (
+        "500000001",
+        USERNAME,
+        "Welcome to the [b]mock thread[/b].\nIt has four posts over two pages.",
+    ),
+    ("500000002", "OtherUser", "Thanks :v:"),
+    (
+        "500000003",
+        USERNAME,
+        "Here is some code:\n[code]let ordered = a < b && b < c;[/code]",
+    ),
+    ("500000004", "OtherUser", "Closing thoughts."),
+];
+
+/// Records changes made by clients.
+struct State {
+    passes: u32,
+    failures: u32,
+    logins: u32,
+    sessions: Vec<String>,
+    replies: Vec<String>,
+    searches: Vec<String>,
+    edits: Vec<HashMap<String, String>>,
+    new_threads: Vec<HashMap<String, String>>,
+    polls: Vec<HashMap<String, String>>,
+    marks: Vec<HashMap<String, String>>,
+    bookmarks: BTreeMap<String, i64>,
+}
+
+/// Serves the mock forums on a local port until dropped.
+pub struct MockServer {
+    base: Url,
+    state: Arc<Mutex<State>>,
+    shutdown: Option<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts a server on a free local port. Must be called from within a
+    /// Tokio runtime.
+    pub fn start() -> std::io::Result<MockServer> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let base = Url::parse(&format!("http://{}/", listener.local_addr()?))
+            .expect("BUG: illegal mock server URL");
+
+        // The bookmarks match the stars shown in the bookmarks fixture.
+        let state = Arc::new(Mutex::new(State {
+            passes: 0,
+            failures: 0,
+            logins: 0,
+            sessions: Vec::new(),
+            replies: Vec::new(),
+            searches: Vec::new(),
+            edits: Vec::new(),
+            new_threads: Vec::new(),
+            polls: Vec::new(),
+            marks: Vec::new(),
+            bookmarks: BTreeMap::from([
+                (String::from(THREAD_ID), 0),
+                (String::from(CLOSED_THREAD_ID), 2),
+            ]),
+        }));
+
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle(state.clone(), request))) }
+        });
+        let (shutdown, stopped) = tokio::sync::oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)
+            .map_err(std::io::Error::other)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                stopped.await.ok();
+            });
+        tokio::spawn(server);
+
+        Ok(MockServer {
+            base,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Returns the base URL of the server.
+    pub fn base_url(&self) -> &Url {
+        &self.base
+    }
+
+    /// Returns a builder for a client that sends requests to the server.
+    /// Requests are not rate limited, and retries wait only briefly, so that
+    /// tests run quickly.
+    pub fn client_builder(&self) -> ClientBuilder {
+        Client::builder()
+            .with_base_url(self.base.clone())
+            .with_rate_limit(RateLimit::unlimited())
+            .with_retry_policy(RetryPolicy::new().with_initial_backoff(Duration::from_millis(10)))
+    }
+
+    /// Makes the next count requests fail with 503 Service Unavailable, as
+    /// when the forums are overloaded.
+    pub fn fail_next_requests(&self, count: u32) {
+        self.fail_requests_after(0, count);
+    }
+
+    /// Lets the next passes requests through, then makes count requests fail
+    /// like fail_next_requests.
+    pub fn fail_requests_after(&self, passes: u32, count: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.passes = passes;
+        state.failures = count;
+    }
+
+    /// Logs out every client, as when session cookies expire. Clients must
+    /// login again before they can access pages.
+    pub fn expire_sessions(&self) {
+        self.state.lock().unwrap().sessions.clear();
+    }
+
+    /// Returns the number of successful logins so far.
+    pub fn logins(&self) -> u32 {
+        self.state.lock().unwrap().logins
+    }
+
+    /// Returns the query strings of all searches so far, in order.
+    pub fn searches(&self) -> Vec<String> {
+        self.state.lock().unwrap().searches.clone()
+    }
+
+    /// Returns the messages of all replies posted so far, in order.
+    pub fn replies(&self) -> Vec<String> {
+        self.state.lock().unwrap().replies.clone()
+    }
+
+    /// Returns the fields of all edits submitted so far, in order.
+    pub fn edits(&self) -> Vec<HashMap<String, String>> {
+        self.state.lock().unwrap().edits.clone()
+    }
+
+    /// Returns the fields of all new threads submitted so far, in order.
+    pub fn new_threads(&self) -> Vec<HashMap<String, String>> {
+        self.state.lock().unwrap().new_threads.clone()
+    }
+
+    /// Returns the fields of all polls submitted so far, in order.
+    pub fn polls(&self) -> Vec<HashMap<String, String>> {
+        self.state.lock().unwrap().polls.clone()
+    }
+
+    /// Returns the parameters of all requests so far that mark a thread or
+    /// forum as read or unread, in order.
+    pub fn marks(&self) -> Vec<HashMap<String, String>> {
+        self.state.lock().unwrap().marks.clone()
+    }
+
+    /// Returns the IDs of all bookmarked threads.
+    pub fn bookmarks(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap()
+            .bookmarks
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the star of a bookmarked thread, or None if the thread is not
+    /// bookmarked.
+    pub fn bookmark_star(&self, thread_id: &str) -> Option<BookmarkStar> {
+        let state = self.state.lock().unwrap();
+        state
+            .bookmarks
+            .get(thread_id)
+            .and_then(|&category| BookmarkStar::from_category(category))
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+    }
+}
+
+/// Routes a request to the emulated page.
+async fn handle(
+    state: Arc<Mutex<State>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    {
+        let mut state = state.lock().unwrap();
+        if state.passes > 0 {
+            state.passes -= 1;
+        } else if state.failures > 0 {
+            state.failures -= 1;
+            return Ok(status(StatusCode::SERVICE_UNAVAILABLE));
+        }
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = hyper::body::to_bytes(body).await.unwrap_or_default();
+
+    let mut params = parts.uri.query().map(parse_form).unwrap_or_default();
+    let content_type = parts
+        .headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .unwrap_or_default();
+    match content_type.split_once("boundary=") {
+        Some((_, boundary)) => params.extend(parse_multipart(&body, boundary)),
+        None => params.extend(parse_form(&String::from_utf8_lossy(&body))),
+    }
+
+    let cookies: HashMap<&str, &str> = parts
+        .headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|x| x.to_str().ok())
+        .flat_map(|x| x.split(';'))
+        .filter_map(|x| x.trim().split_once('='))
+        .collect();
+    let logged_in = cookies.get("bbuserid") == Some(&USER_ID)
+        && cookies.get("bbpassword").is_some_and(|session| {
+            let state = state.lock().unwrap();
+            state.sessions.iter().any(|x| x == session)
+        });
+
+    let response = match (&parts.method, parts.uri.path()) {
+        (&Method::POST, "/account.php") => login(&state, &params),
+        _ if !logged_in => html(fixtures::NOT_LOGGED_IN),
+        (&Method::GET, "/index.php") => html(fixtures::FORUM_INDEX),
+        (&Method::GET, "/member.php") => profile(&params),
+        (&Method::GET, "/showthread.php") if param(&params, "action") == "setseen" => {
+            mark(&state, &params)
+        }
+        (&Method::GET, "/showthread.php") => thread_page(&state, &params),
+        (&Method::POST, "/showthread.php") if param(&params, "action") == "resetseen" => {
+            mark(&state, &params)
+        }
+        (&Method::GET, "/forumdisplay.php") if param(&params, "action") == "markread" => {
+            mark(&state, &params)
+        }
+        (&Method::GET, "/bookmarkthreads.php") => html(fixtures::BOOKMARKS),
+        (&Method::POST, "/bookmarkthreads.php") => update_bookmark(&state, &params),
+        (&Method::GET, "/newreply.php") => reply_form(&params),
+        (&Method::POST, "/newreply.php") => post_reply(&state, &params),
+        (&Method::GET, "/editpost.php") => edit_form(&params),
+        (&Method::POST, "/editpost.php") => post_edit(&state, &params),
+        (&Method::GET, "/newthread.php") => new_thread_form(&params),
+        (&Method::POST, "/newthread.php") => post_new_thread(&state, &params),
+        (&Method::POST, "/poll.php") => post_poll(&state, &params),
+        (&Method::POST, "/query.php") => search(&state, &params),
+        (&Method::GET, "/query.php") if param(&params, "qid") == QUERY_ID => {
+            html(fixtures::SEARCH_RESULTS)
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    };
+    Ok(response)
+}
+
+/// Starts a new session and sets its cookies if the credentials match the
+/// account.
+fn login(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "username") != USERNAME || param(params, "password") != PASSWORD {
+        return status(StatusCode::UNAUTHORIZED);
+    }
+    let session = {
+        let mut state = state.lock().unwrap();
+        state.logins += 1;
+        let session = format!("{:016x}", state.logins);
+        state.sessions.push(session.clone());
+        session
+    };
+    // The cookies are persistent, like the forums' cookies, so that they
+    // can be saved with the client's credentials.
+    Response::builder()
+        .header(
+            header::SET_COOKIE,
+            format!("bbuserid={USER_ID}; Path=/; Max-Age=31536000"),
+        )
+        .header(
+            header::SET_COOKIE,
+            format!("bbpassword={session}; Path=/; Max-Age=31536000"),
+        )
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from("{}"))
+        .expect("BUG: illegal response")
+}
+
+/// Returns the profile of the account, which is the only user.
+fn profile(params: &HashMap<String, String>) -> Response<Body> {
+    let other_user = params.get("username").is_some_and(|x| x != USERNAME)
+        || params.get("userid").is_some_and(|x| x != USER_ID);
+    if other_user {
+        html(fixtures::USER_NOT_FOUND)
+    } else {
+        json(fixtures::PROFILE)
+    }
+}
+
+/// Returns the requested page of the thread. Pages past the end are clamped
+/// to the last page, as on the forums.
+fn thread_page(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "threadid") != THREAD_ID && param(params, "goto") != "post" {
+        return html(fixtures::THREAD_NOT_FOUND);
+    }
+    let page = match param(params, "goto") {
+        // The last post is the first unseen post.
+        "lastpost" | "newpost" => 2,
+        "post" => match param(params, "postid").parse::<u64>() {
+            Ok(post_id) if post_id >= SECOND_PAGE_POST_ID => 2,
+            _ => 1,
+        },
+        _ => param(params, "pagenumber").parse().unwrap_or(1),
+    };
+    if page <= 1 {
+        return html(fixtures::THREAD_PAGE_1);
+    }
+
+    let state = state.lock().unwrap();
+    let replies: String = state
+        .replies
+        .iter()
+        .enumerate()
+        .map(|(i, message)| {
+            let i = i as u64 + 1;
+            reply_html(LAST_POST_ID + i, FIXTURE_POSTS + i, message)
+        })
+        .collect();
+    let bottom = r#"<div class="pages bottom">"#;
+    html(&fixtures::THREAD_PAGE_2.replacen(bottom, &format!("{replies}{bottom}"), 1))
+}
+
+/// Renders a reply by the account as it appears in a thread.
+fn reply_html(post_id: u64, index: u64, message: &str) -> String {
+    let message = message
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    format!(
+        r##"<table class="post" id="post{post_id}" data-idx="{index}">
+<tbody>
+<tr class="seen1">
+<td class="userinfo userid-{USER_ID}">
+<dl class="userinfo">
+<dt class="author platinum">{USERNAME}</dt>
+<dd class="registered">Jun 18, 2003</dd>
+</dl>
+</td>
+<td class="postbody">
+{message}
+</td>
+</tr>
+<tr class="seen1">
+<td class="postdate"><a href="#post{post_id}" title="Link to this post">#</a> Jun 20, 2023 12:00</td>
+</tr>
+</tbody>
+</table>
+"##
+    )
+}
+
+/// Records a request that marks the thread or a forum as read or unread.
+fn mark(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if params.contains_key("threadid") && param(params, "threadid") != THREAD_ID {
+        return html(fixtures::THREAD_NOT_FOUND);
+    }
+    state.lock().unwrap().marks.push(params.clone());
+    json("{}")
+}
+
+/// Adds, removes or cycles the star of a bookmark.
+fn update_bookmark(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    let thread_id = param(params, "threadid").to_owned();
+    let mut state = state.lock().unwrap();
+    match param(params, "action") {
+        "add" => {
+            state.bookmarks.entry(thread_id).or_insert(0);
+            json("{}")
+        }
+        "remove" => {
+            state.bookmarks.remove(&thread_id);
+            json("{}")
+        }
+        "cat_toggle" => {
+            let Some(category) = state.bookmarks.get_mut(&thread_id) else {
+                return status(StatusCode::BAD_REQUEST);
+            };
+            *category = (*category + 1) % BookmarkStar::ALL.len() as i64;
+            json(&format!(r#"{{"category_id":{category}}}"#))
+        }
+        _ => status(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Returns the reply form, or the page explaining why the thread cannot be
+/// replied to. Requests for a post ID return the form quoting that post.
+fn reply_form(params: &HashMap<String, String>) -> Response<Body> {
+    if params.contains_key("postid") {
+        return quote_form(param(params, "postid"));
+    }
+    match param(params, "threadid") {
+        THREAD_ID => html(fixtures::NEW_REPLY),
+        CLOSED_THREAD_ID => html(fixtures::THREAD_CLOSED),
+        _ => html(fixtures::THREAD_NOT_FOUND),
+    }
+}
+
+/// Returns the reply form prefilled with a quote of a post. Forms for posts
+/// other than the captured one are the captured form with the quote replaced.
+fn quote_form(post_id: &str) -> Response<Body> {
+    let quote = |post_id: &str| {
+        let (_, author, source) = POST_SOURCES.iter().find(|(id, _, _)| *id == post_id)?;
+        let quote = format!("[quote=\"{author}\" post=\"{post_id}\"]\n{source}\n[/quote]\n");
+        Some(
+            quote
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;"),
+        )
+    };
+    let Some(requested) = quote(post_id) else {
+        return html(fixtures::THREAD_NOT_FOUND);
+    };
+    let captured = quote("500000002").expect("BUG: missing captured post");
+    html(&fixtures::NEW_REPLY_QUOTE.replacen(&captured, &requested, 1))
+}
+
+/// Records a reply submitted through the reply form.
+fn post_reply(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "threadid") != THREAD_ID || param(params, "formkey") != FORM_KEY {
+        return status(StatusCode::BAD_REQUEST);
+    }
+    let message = param(params, "message");
+    if message.trim().is_empty() {
+        let errors = r#"<div class="errors"><ul><li>The message you have entered is too short.</li></ul></div>"#;
+        return html(&fixtures::NEW_REPLY.replacen("<form", &format!("{errors}\n<form"), 1));
+    }
+    let mut state = state.lock().unwrap();
+    state.replies.push(message.to_owned());
+    let post_id = LAST_POST_ID + state.replies.len() as u64;
+
+    thanks(
+        &format!("showthread.php?goto=post&amp;postid={post_id}#post{post_id}"),
+        "your post",
+    )
+}
+
+/// Returns the edit form of the editable post.
+fn edit_form(params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "action") != "editpost" || param(params, "postid") != EDITABLE_POST_ID {
+        return html(fixtures::NO_PERMISSION);
+    }
+    html(fixtures::EDIT_POST)
+}
+
+/// Records an edit submitted through the edit form.
+fn post_edit(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "postid") != EDITABLE_POST_ID || param(params, "formkey") != FORM_KEY {
+        return status(StatusCode::BAD_REQUEST);
+    }
+    state.lock().unwrap().edits.push(params.clone());
+    thanks(
+        &format!("showthread.php?goto=post&amp;postid={EDITABLE_POST_ID}#post{EDITABLE_POST_ID}"),
+        "your post",
+    )
+}
+
+/// Returns the new thread form of the mock forum.
+fn new_thread_form(params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "action") != "newthread" || param(params, "forumid") != FORUM_ID {
+        return html(fixtures::NO_PERMISSION);
+    }
+    html(fixtures::NEW_THREAD)
+}
+
+/// Records a thread submitted through the new thread form. Threads with polls
+/// are followed by the poll form, as on the forums.
+fn post_new_thread(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "forumid") != FORUM_ID || param(params, "formkey") != FORM_KEY {
+        return status(StatusCode::BAD_REQUEST);
+    }
+    state.lock().unwrap().new_threads.push(params.clone());
+    if param(params, "postpoll") == "yes" {
+        return html(fixtures::NEW_POLL);
+    }
+    thanks(
+        &format!("showthread.php?threadid={NEW_THREAD_ID}"),
+        "your thread",
+    )
+}
+
+/// Records a poll submitted through the poll form.
+fn post_poll(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "threadid") != NEW_THREAD_ID || param(params, "formkey") != FORM_KEY {
+        return status(StatusCode::BAD_REQUEST);
+    }
+    state.lock().unwrap().polls.push(params.clone());
+    thanks(
+        &format!("showthread.php?threadid={NEW_THREAD_ID}"),
+        "your thread",
+    )
+}
+
+/// Records a search. The first page of results is shown without redirecting,
+/// so clients must find the query ID in the page's links.
+fn search(state: &Mutex<State>, params: &HashMap<String, String>) -> Response<Body> {
+    if param(params, "action") != "query" {
+        return status(StatusCode::BAD_REQUEST);
+    }
+    let mut state = state.lock().unwrap();
+    state.searches.push(param(params, "q").to_owned());
+    html(fixtures::SEARCH_RESULTS)
+}
+
+/// Returns the page shown after posting, which redirects to the given URL.
+fn thanks(url: &str, description: &str) -> Response<Body> {
+    html(&format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta http-equiv="Refresh" content="1; URL={url}">
+<title>The Something Awful Forums</title>
+</head>
+<body>
+<div class="standard">Thank you for posting! You will now be taken to <a href="{url}">{description}</a>.</div>
+</body>
+</html>
+"#
+    ))
+}
+
+/// Returns a parameter, or an empty string if it is missing.
+fn param<'a>(params: &'a HashMap<String, String>, name: &str) -> &'a str {
+    params.get(name).map(String::as_str).unwrap_or_default()
+}
+
+/// Parses URL-encoded parameters.
+fn parse_form(encoded: &str) -> HashMap<String, String> {
+    url::form_urlencoded::parse(encoded.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// Parses the text fields of a multipart body.
+fn parse_multipart(body: &[u8], boundary: &str) -> HashMap<String, String> {
+    let body = String::from_utf8_lossy(body);
+    body.split(&format!("--{boundary}"))
+        .filter_map(|part| {
+            let (headers, value) = part.split_once("\r\n\r\n")?;
+            let name = headers.split("name=\"").nth(1)?.split('"').next()?;
+            let value = value.strip_suffix("\r\n").unwrap_or(value);
+            Some((name.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+fn html(body: &str) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(body.to_owned()))
+        .expect("BUG: illegal response")
+}
+
+fn json(body: &str) -> Response<Body> {
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_owned()))
+        .expect("BUG: illegal response")
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .expect("BUG: illegal response")
+}
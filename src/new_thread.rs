@@ -0,0 +1,258 @@
+use crate::{reply::Reply, scrape, Error};
+
+/// Represents a post icon, which doubles as the thread tag shown next to the
+/// thread in forum listings.
+#[derive(Debug, Clone)]
+pub struct PostIcon {
+    pub id: String,
+    pub title: String,
+    pub image_url: String,
+}
+
+/// New thread parameters read from the server.
+pub struct NewThreadParams {
+    action: String,
+    forumid: String,
+    formkey: String,
+    form_cookie: String,
+
+    /// Post icons that the forum accepts. The first choice is usually "no
+    /// icon", which has an ID of 0.
+    pub icons: Vec<PostIcon>,
+}
+
+impl NewThreadParams {
+    /// Prepares a new thread by parsing a new thread form for metadata that
+    /// must be sent back in the new thread request.
+    pub fn parse(document: &str) -> Result<NewThreadParams, Error> {
+        let document = scraper::Html::parse_document(document);
+        let Some(form) = scrape::form(&document, "vbform") else {
+            return Err(Error::NewThreadParsingError);
+        };
+        let Some(action) = scrape::input_value(&form, "action") else {
+            return Err(Error::NewThreadParsingError);
+        };
+        let Some(forumid) = scrape::input_value(&form, "forumid") else {
+            return Err(Error::NewThreadParsingError);
+        };
+        let Some(formkey) = scrape::input_value(&form, "formkey") else {
+            return Err(Error::NewThreadParsingError);
+        };
+        let Some(form_cookie) = scrape::input_value(&form, "form_cookie") else {
+            return Err(Error::NewThreadParsingError);
+        };
+
+        let selector =
+            scraper::Selector::parse(r#"input[name="iconid"]"#).expect("BUG: illegal selector");
+        let image = scraper::Selector::parse(r#"img"#).expect("BUG: illegal selector");
+        let mut icons = Vec::new();
+        for input in form.select(&selector) {
+            let Some(id) = input.value().attr("value") else {
+                continue;
+            };
+            // Each radio button shares a container with the icon it selects.
+            let image = input
+                .parent()
+                .and_then(scraper::ElementRef::wrap)
+                .and_then(|parent| parent.select(&image).next());
+            let title = image
+                .and_then(|x| x.value().attr("title").or(x.value().attr("alt")))
+                .unwrap_or_default();
+            let image_url = image
+                .and_then(|x| x.value().attr("src"))
+                .unwrap_or_default();
+            icons.push(PostIcon {
+                id: id.to_owned(),
+                title: title.to_owned(),
+                image_url: image_url.to_owned(),
+            });
+        }
+
+        Ok(NewThreadParams {
+            action,
+            forumid,
+            formkey,
+            form_cookie,
+            icons,
+        })
+    }
+
+    /// Consumes the builder and a new thread, returning a form corresponding
+    /// to the new thread payload along with the poll to submit afterward, if
+    /// any. Returns InvalidPostIcon if the thread uses an icon that the forum
+    /// does not accept.
+    pub fn into_form(
+        self,
+        thread: NewThread,
+    ) -> Result<(reqwest::multipart::Form, Option<Poll>), Error> {
+        if let Some(icon) = thread.icon.as_ref() {
+            if !self.icons.iter().any(|x| &x.id == icon) {
+                return Err(Error::InvalidPostIcon(icon.clone()));
+            }
+        }
+
+        let reply = thread.reply;
+        let (attachment_filename, attachment_contents) =
+            reply.attachment.unwrap_or((String::new(), Vec::new()));
+        let mut form = reqwest::multipart::Form::new()
+            .text("action", self.action)
+            .text("forumid", self.forumid)
+            .text("formkey", self.formkey)
+            .text("form_cookie", self.form_cookie)
+            .text("subject", thread.title)
+            .text("iconid", thread.icon.unwrap_or(String::from("0")))
+            .text("message", reply.message)
+            .text("parseurl", "yes")
+            .text("bookmark", if reply.bookmark { "yes" } else { "no" })
+            .text("submit", "Submit New Thread")
+            .part(
+                "attachment",
+                reqwest::multipart::Part::bytes(attachment_contents)
+                    .file_name(attachment_filename)
+                    .mime_str("application/octet-stream")?,
+            );
+        if let Some(poll) = thread.poll.as_ref() {
+            form = form
+                .text("postpoll", "yes")
+                .text("polloptions", format!("{}", poll.options.len()));
+        }
+        Ok((form, thread.poll))
+    }
+}
+
+/// Poll parameters read from the server after a thread with a poll is
+/// created.
+pub struct PollParams {
+    hidden: Vec<(String, String)>,
+}
+
+impl PollParams {
+    /// Prepares a poll by parsing the poll form shown after creating a thread.
+    pub fn parse(document: &str) -> Result<PollParams, Error> {
+        let document = scraper::Html::parse_document(document);
+        let Some(form) = scrape::form(&document, "vbform") else {
+            return Err(Error::NewThreadParsingError);
+        };
+        let hidden = scrape::hidden_inputs(&form);
+        if !hidden.iter().any(|(name, _)| name == "threadid") {
+            return Err(Error::NewThreadParsingError);
+        }
+        Ok(PollParams { hidden })
+    }
+
+    /// Consumes the builder and a poll, returning the fields of the poll
+    /// payload.
+    pub fn into_form(self, poll: Poll) -> Vec<(String, String)> {
+        let mut form = self.hidden;
+        form.retain(|(name, _)| name != "polloptions");
+        form.push((String::from("question"), poll.question));
+        form.push((
+            String::from("polloptions"),
+            format!("{}", poll.options.len()),
+        ));
+        for (i, option) in poll.options.into_iter().enumerate() {
+            form.push((format!("options[{}]", i + 1), option));
+        }
+        if poll.multiple_choice {
+            form.push((String::from("multiple"), String::from("yes")));
+        }
+        form.push((
+            String::from("timeout"),
+            format!("{}", poll.timeout_days.unwrap_or(0)),
+        ));
+        form.push((String::from("submit"), String::from("Submit New Poll")));
+        form
+    }
+}
+
+/// Represents a poll attached to a new thread.
+pub struct Poll {
+    question: String,
+    options: Vec<String>,
+    multiple_choice: bool,
+    timeout_days: Option<u32>,
+}
+
+impl Poll {
+    /// Creates a single choice poll with no time limit.
+    pub fn new(question: &str, options: &[&str]) -> Poll {
+        Poll {
+            question: question.to_owned(),
+            options: options.iter().map(|x| (*x).to_owned()).collect(),
+            multiple_choice: false,
+            timeout_days: None,
+        }
+    }
+
+    /// Sets whether voters may choose more than one option. Defaults to false.
+    pub fn with_multiple_choice(mut self, multiple_choice: bool) -> Self {
+        self.multiple_choice = multiple_choice;
+        self
+    }
+
+    /// Closes the poll after the given number of days. Defaults to never
+    /// closing.
+    pub fn with_timeout_days(mut self, days: u32) -> Self {
+        self.timeout_days = Some(days);
+        self
+    }
+
+    /// Checks that the question and every option have text. Returns
+    /// InvalidPoll describing the first problem found.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.question.trim().is_empty() {
+            return Err(Error::InvalidPoll(String::from("the question is blank")));
+        }
+        if self.options.is_empty() {
+            return Err(Error::InvalidPoll(String::from("the poll has no options")));
+        }
+        if let Some(i) = self.options.iter().position(|x| x.trim().is_empty()) {
+            return Err(Error::InvalidPoll(format!("option {} is blank", i + 1)));
+        }
+        Ok(())
+    }
+}
+
+/// Represents a thread that the user will start.
+pub struct NewThread {
+    title: String,
+    reply: Reply,
+    icon: Option<String>,
+    poll: Option<Poll>,
+}
+
+impl NewThread {
+    /// Creates a thread with the given title whose first post is the given
+    /// reply.
+    pub fn new(title: &str, reply: Reply) -> NewThread {
+        NewThread {
+            title: title.to_owned(),
+            reply,
+            icon: None,
+            poll: None,
+        }
+    }
+
+    /// Sets the post icon by ID, overriding any existing icon. Defaults to no
+    /// icon.
+    pub fn with_icon(mut self, icon_id: &str) -> Self {
+        self.icon = Some(icon_id.to_owned());
+        self
+    }
+
+    /// Checks the message of the first post and the poll, if any. See
+    /// Reply::validate and Poll::validate.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.reply.validate()?;
+        if let Some(poll) = self.poll.as_ref() {
+            poll.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Attaches a poll, overriding any existing poll. Defaults to no poll.
+    pub fn with_poll(mut self, poll: Poll) -> Self {
+        self.poll = Some(poll);
+        self
+    }
+}
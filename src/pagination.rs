@@ -0,0 +1,66 @@
+/// The number of posts or threads on each page of a listing unless
+/// configured otherwise.
+pub const DEFAULT_PER_PAGE: usize = 40;
+
+/// Describes where a page sits within a paginated listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pagination {
+    /// The current page, starting from 1.
+    pub current_page: usize,
+
+    /// The total number of pages.
+    pub total_pages: usize,
+}
+
+impl Pagination {
+    /// Parses the pager on a page. Pages without a pager only have a single
+    /// page.
+    pub fn parse(document: &str) -> Pagination {
+        let document = scraper::Html::parse_document(document);
+        Pagination::parse_html(&document)
+    }
+
+    /// Parses the pager within an already parsed document.
+    pub(crate) fn parse_html(document: &scraper::Html) -> Pagination {
+        let selector =
+            scraper::Selector::parse(r#"div.pages select"#).expect("BUG: illegal selector");
+        let Some(pager) = document.select(&selector).next() else {
+            return Pagination {
+                current_page: 1,
+                total_pages: 1,
+            };
+        };
+
+        let selector = scraper::Selector::parse(r#"option"#).expect("BUG: illegal selector");
+        let mut current_page = pager
+            .value()
+            .attr("data-page")
+            .and_then(|page| page.parse().ok());
+        let mut total_pages = 1;
+        for option in pager.select(&selector) {
+            let Some(page) = option.value().attr("value").and_then(|x| x.parse().ok()) else {
+                continue;
+            };
+            if option.value().attr("selected").is_some() {
+                current_page = Some(page);
+            }
+            total_pages = total_pages.max(page);
+        }
+
+        Pagination {
+            current_page: current_page.unwrap_or(1),
+            total_pages,
+        }
+    }
+
+    /// Returns whether there are pages after the current page.
+    pub fn has_next_page(&self) -> bool {
+        self.current_page < self.total_pages
+    }
+
+    /// Returns the page that holds the item with the given index, counting
+    /// from 1, when each page holds per_page items.
+    pub fn page_of(index: usize, per_page: usize) -> usize {
+        index.saturating_sub(1) / per_page.max(1) + 1
+    }
+}
@@ -0,0 +1,344 @@
+use crate::{scrape, timestamp::Timestamp, Error};
+
+/// References a private message folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Folder {
+    /// Messages received by the user.
+    Inbox,
+
+    /// Copies of messages sent by the user.
+    Sent,
+
+    /// A folder created by the user.
+    Custom(i64),
+}
+
+impl Folder {
+    /// Returns the folder ID used by the server.
+    pub(crate) fn id(&self) -> String {
+        match self {
+            Folder::Inbox => String::from("0"),
+            Folder::Sent => String::from("-1"),
+            Folder::Custom(id) => format!("{id}"),
+        }
+    }
+}
+
+/// Indicates what the user has done with a private message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStatus {
+    Unread,
+    Read,
+    Replied,
+    Forwarded,
+}
+
+/// Represents a single private message within a folder.
+#[derive(Debug)]
+pub struct MessageSummary {
+    pub id: String,
+    pub title: String,
+
+    /// The sender of a received message, or the recipient of a sent message.
+    pub correspondent_username: String,
+
+    pub date: Timestamp,
+    pub status: MessageStatus,
+}
+
+/// Represents the full contents of a private message.
+#[derive(Debug)]
+pub struct Message {
+    pub id: String,
+    pub title: String,
+    pub sender_username: String,
+    pub date: Timestamp,
+
+    /// The inner HTML of the message, in the same form as a post body.
+    pub body: String,
+}
+
+impl MessageSummary {
+    /// Parses all messages listed in a private message folder.
+    pub fn parse_list(document: &str) -> Result<Vec<MessageSummary>, Error> {
+        let mut messages = Vec::new();
+        let document = scraper::Html::parse_document(document);
+        let selector =
+            scraper::Selector::parse(r#"table#pms>tbody>tr"#).expect("BUG: illegal selector");
+
+        for message in document.select(&selector) {
+            let parsing_error = |reason: &str| Error::PrivateMessageParsingError(reason.to_owned());
+
+            let selector =
+                scraper::Selector::parse(r#"td.title a"#).expect("BUG: illegal selector");
+            let Some(title) = message.select(&selector).next() else {
+                return Err(parsing_error("missing message title"));
+            };
+            let Some(id) = title
+                .value()
+                .attr("href")
+                .and_then(|href| scrape::query_param(href, "privatemessageid"))
+            else {
+                return Err(parsing_error("missing message ID"));
+            };
+            let title = scrape::trimmed_text(&title);
+
+            let selector = scraper::Selector::parse(r#"td.sender"#).expect("BUG: illegal selector");
+            let Some(correspondent_username) = message.select(&selector).next() else {
+                return Err(parsing_error("missing correspondent"));
+            };
+            let correspondent_username = scrape::trimmed_text(&correspondent_username);
+
+            let selector = scraper::Selector::parse(r#"td.date"#).expect("BUG: illegal selector");
+            let Some(date) = message.select(&selector).next() else {
+                return Err(parsing_error("missing message date"));
+            };
+            let date = Timestamp::parse(&scrape::trimmed_text(&date));
+
+            let selector =
+                scraper::Selector::parse(r#"td.status img"#).expect("BUG: illegal selector");
+            let status = message
+                .select(&selector)
+                .next()
+                .and_then(|x| x.value().attr("src"))
+                .unwrap_or_default();
+            let status = if status.contains("newpm") {
+                MessageStatus::Unread
+            } else if status.contains("replied") {
+                MessageStatus::Replied
+            } else if status.contains("forwarded") {
+                MessageStatus::Forwarded
+            } else {
+                MessageStatus::Read
+            };
+
+            messages.push(MessageSummary {
+                id,
+                title,
+                correspondent_username,
+                date,
+                status,
+            });
+        }
+
+        Ok(messages)
+    }
+}
+
+impl Message {
+    /// Parses a page showing the private message with the given ID.
+    pub fn parse(document: &str, id: &str) -> Result<Message, Error> {
+        let document = scraper::Html::parse_document(document);
+        let parsing_error = || Error::PrivateMessageParsingError(String::from("message not found"));
+
+        let selector = scraper::Selector::parse(r#"table.post"#).expect("BUG: illegal selector");
+        let Some(message) = document.select(&selector).next() else {
+            return Err(parsing_error());
+        };
+
+        // The last breadcrumb links to the message itself.
+        let selector =
+            scraper::Selector::parse(r#"div.breadcrumbs a"#).expect("BUG: illegal selector");
+        let Some(title) = document.select(&selector).next_back() else {
+            return Err(parsing_error());
+        };
+        let title = scrape::trimmed_text(&title);
+
+        let selector =
+            scraper::Selector::parse(r#"dl.userinfo>dt"#).expect("BUG: illegal selector");
+        let Some(sender_username) = message.select(&selector).next() else {
+            return Err(parsing_error());
+        };
+        let sender_username = scrape::trimmed_text(&sender_username);
+
+        let selector = scraper::Selector::parse(r#"td.postdate"#).expect("BUG: illegal selector");
+        let Some(date) = message.select(&selector).next() else {
+            return Err(parsing_error());
+        };
+        let Some(date) = date.text().last() else {
+            return Err(parsing_error());
+        };
+        let date = Timestamp::parse(date);
+
+        let selector = scraper::Selector::parse(r#"td.postbody"#).expect("BUG: illegal selector");
+        let Some(body) = message.select(&selector).next() else {
+            return Err(parsing_error());
+        };
+        let body = body.inner_html();
+
+        Ok(Message {
+            id: id.to_owned(),
+            title,
+            sender_username,
+            date,
+            body,
+        })
+    }
+}
+
+/// Message parameters read from the server.
+pub struct MessageParams {
+    action: String,
+    formkey: String,
+    form_cookie: String,
+
+    /// Set when replying to or forwarding a message.
+    prevmessageid: Option<String>,
+
+    /// The recipient prefilled when replying to a message.
+    touser: Option<String>,
+
+    /// The title prefilled when replying to a message.
+    title: Option<String>,
+}
+
+impl MessageParams {
+    /// Prepares a message by parsing a message form for metadata that must be
+    /// sent back in the send request.
+    pub fn parse(document: &str) -> Result<MessageParams, Error> {
+        let document = scraper::Html::parse_document(document);
+        let Some(form) = scrape::form(&document, "vbform") else {
+            return Err(Error::PrivateMessageParsingError(String::from(
+                "message form not found",
+            )));
+        };
+        let Some(action) = scrape::input_value(&form, "action") else {
+            return Err(Error::PrivateMessageParsingError(String::from(
+                "missing action",
+            )));
+        };
+        let Some(formkey) = scrape::input_value(&form, "formkey") else {
+            return Err(Error::PrivateMessageParsingError(String::from(
+                "missing formkey",
+            )));
+        };
+        let Some(form_cookie) = scrape::input_value(&form, "form_cookie") else {
+            return Err(Error::PrivateMessageParsingError(String::from(
+                "missing form_cookie",
+            )));
+        };
+
+        Ok(MessageParams {
+            action,
+            formkey,
+            form_cookie,
+            prevmessageid: scrape::input_value(&form, "prevmessageid"),
+            touser: scrape::input_value(&form, "touser"),
+            title: scrape::input_value(&form, "title"),
+        })
+    }
+
+    /// Consumes the builder and a message, returning the fields of the send
+    /// message payload.
+    pub fn into_form(self, message: NewMessage) -> Vec<(&'static str, String)> {
+        let mut form = vec![
+            ("action", self.action),
+            ("formkey", self.formkey),
+            ("form_cookie", self.form_cookie),
+            ("touser", message.recipient),
+            ("title", message.title),
+            ("message", message.message),
+            ("parseurl", String::from("yes")),
+            (
+                "savecopy",
+                String::from(if message.save_copy { "yes" } else { "no" }),
+            ),
+            ("submit", String::from("Send Message")),
+        ];
+        if let Some(prevmessageid) = self.prevmessageid {
+            form.push(("prevmessageid", prevmessageid));
+        }
+        form
+    }
+
+    /// Returns a message to the prefilled recipient with the prefilled title,
+    /// or None if the form was not prefilled.
+    pub fn prefilled(&self, message: &str) -> Option<NewMessage> {
+        let recipient = self.touser.as_ref()?;
+        let title = self.title.as_ref()?;
+        Some(NewMessage::new(recipient, title, message))
+    }
+}
+
+/// Changes that can be made to messages within a folder.
+pub enum FolderAction {
+    /// Permanently deletes the messages.
+    Delete,
+
+    /// Moves the messages to another folder.
+    Move(Folder),
+}
+
+/// Folder management parameters read from the server.
+pub struct FolderParams {
+    hidden: Vec<(String, String)>,
+}
+
+impl FolderParams {
+    /// Prepares a folder change by parsing the form that encloses a folder's
+    /// message list.
+    pub fn parse(document: &str) -> Result<FolderParams, Error> {
+        let document = scraper::Html::parse_document(document);
+        let selector = scraper::Selector::parse(r#"form"#).expect("BUG: illegal selector");
+        let messages = scraper::Selector::parse(r#"table#pms"#).expect("BUG: illegal selector");
+        let Some(form) = document
+            .select(&selector)
+            .find(|form| form.select(&messages).next().is_some())
+        else {
+            return Err(Error::PrivateMessageParsingError(String::from(
+                "folder form not found",
+            )));
+        };
+
+        Ok(FolderParams {
+            hidden: scrape::hidden_inputs(&form),
+        })
+    }
+
+    /// Consumes the builder, returning the fields of a request that applies
+    /// an action to the given messages.
+    pub fn into_form(self, message_ids: &[&str], action: FolderAction) -> Vec<(String, String)> {
+        let mut form = self.hidden;
+        for id in message_ids {
+            form.push((format!("privatemessage[{id}]"), String::from("yes")));
+        }
+        match action {
+            FolderAction::Delete => {
+                form.push((String::from("delete"), String::from("Delete")));
+            }
+            FolderAction::Move(folder) => {
+                form.retain(|(name, _)| name != "folderid");
+                form.push((String::from("folderid"), folder.id()));
+                form.push((String::from("move"), String::from("Move")));
+            }
+        }
+        form
+    }
+}
+
+/// Represents a private message that the user will send.
+pub struct NewMessage {
+    recipient: String,
+    title: String,
+    message: String,
+    save_copy: bool,
+}
+
+impl NewMessage {
+    /// Creates a message to the given username with the given BBCode message.
+    pub fn new(recipient: &str, title: &str, message: &str) -> NewMessage {
+        NewMessage {
+            recipient: recipient.to_owned(),
+            title: title.to_owned(),
+            message: message.to_owned(),
+            save_copy: true,
+        }
+    }
+
+    /// Sets whether a copy of the message should be saved to the sent
+    /// folder. Defaults to true.
+    pub fn with_save_copy(mut self, save_copy: bool) -> Self {
+        self.save_copy = save_copy;
+        self
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{scrape, timestamp::Timestamp};
+use scraper::ElementRef;
+
+/// Represents the parsed contents of a post body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostBody {
+    /// Top-level content. Runs of inline content are grouped into
+    /// paragraphs, separated by block content such as quotes and code.
+    pub nodes: Vec<Node>,
+
+    /// The notice shown when the post was edited, if any.
+    pub edited: Option<EditNotice>,
+}
+
+/// Represents a piece of content within a post body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A run of inline content between block content.
+    Paragraph(Vec<Node>),
+
+    /// Plain text.
+    Text(String),
+
+    /// A line break.
+    LineBreak,
+
+    Bold(Vec<Node>),
+    Italic(Vec<Node>),
+    Underline(Vec<Node>),
+    Strikethrough(Vec<Node>),
+    Superscript(Vec<Node>),
+    Subscript(Vec<Node>),
+
+    /// Monospaced inline text.
+    Fixed(Vec<Node>),
+
+    /// Text that is hidden until hovered.
+    Spoiler(Vec<Node>),
+
+    /// A quote of another post, or of arbitrary text if author is None.
+    Quote {
+        author: Option<String>,
+        post_id: Option<String>,
+        children: Vec<Node>,
+    },
+
+    /// A preformatted block, such as code.
+    Code {
+        kind: CodeKind,
+        text: String,
+    },
+
+    /// An image. Thumbnails are shown shrunk until clicked.
+    Image {
+        url: String,
+        thumbnail: bool,
+    },
+
+    /// A hyperlink.
+    Link {
+        url: String,
+        children: Vec<Node>,
+    },
+
+    /// An embedded video.
+    Video {
+        url: String,
+    },
+
+    /// A forum smilie, such as ":v:".
+    Smilie {
+        code: String,
+        url: String,
+    },
+
+    /// A bulleted or numbered list, where each item is a list of nodes.
+    List {
+        ordered: bool,
+        items: Vec<Vec<Node>>,
+    },
+}
+
+/// Distinguishes the kinds of preformatted blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeKind {
+    Code,
+    Php,
+    Pre,
+}
+
+/// Represents the notice shown when a post was edited.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditNotice {
+    /// The full text of the notice.
+    pub text: String,
+
+    /// The user who edited the post, or None if the notice was not in a
+    /// known format.
+    pub username: Option<String>,
+
+    /// The time of the most recent edit, or None if the notice was not in a
+    /// known format.
+    pub date: Option<Timestamp>,
+}
+
+impl PostBody {
+    /// Parses the inner HTML of a post body.
+    pub fn parse(html: &str) -> PostBody {
+        let fragment = scraper::Html::parse_fragment(html);
+        let mut edited = None;
+        let mut inline = Vec::new();
+        let mut nodes = Vec::new();
+
+        for child in fragment.root_element().children() {
+            if let Some(element) = ElementRef::wrap(child) {
+                if has_class(&element, "editedby") {
+                    edited = Some(EditNotice::parse(&scrape::trimmed_text(&element)));
+                    continue;
+                }
+            }
+            for node in parse_node(child) {
+                if node.is_block() {
+                    flush_paragraph(&mut inline, &mut nodes);
+                    nodes.push(node);
+                } else {
+                    inline.push(node);
+                }
+            }
+        }
+        flush_paragraph(&mut inline, &mut nodes);
+
+        PostBody { nodes, edited }
+    }
+}
+
+impl Node {
+    /// Returns whether the node is laid out as its own block, rather than
+    /// within a paragraph.
+    pub fn is_block(&self) -> bool {
+        matches!(
+            self,
+            Node::Paragraph(_)
+                | Node::Quote { .. }
+                | Node::Code { .. }
+                | Node::List { .. }
+                | Node::Video { .. }
+        )
+    }
+}
+
+impl EditNotice {
+    /// Parses the text of an edit notice, such as "Username fucked around
+    /// with this message at 22:08 on Jun 18, 2023".
+    pub fn parse(text: &str) -> EditNotice {
+        const SEPARATOR: &str = " fucked around with this message at ";
+        let text = text.trim();
+        let (username, date) = match text.split_once(SEPARATOR) {
+            Some((username, date)) => (Some(username.to_owned()), Some(Timestamp::parse(date))),
+            None => (None, None),
+        };
+        EditNotice {
+            text: text.to_owned(),
+            username,
+            date,
+        }
+    }
+}
+
+/// Moves accumulated inline content into a paragraph, trimming whitespace
+/// left over from the page layout.
+fn flush_paragraph(inline: &mut Vec<Node>, nodes: &mut Vec<Node>) {
+    if let Some(Node::Text(text)) = inline.first_mut() {
+        *text = text.trim_start().to_owned();
+    }
+    if let Some(Node::Text(text)) = inline.last_mut() {
+        *text = text.trim_end().to_owned();
+    }
+    inline.retain(|node| !matches!(node, Node::Text(text) if text.is_empty()));
+    if !inline.is_empty() {
+        nodes.push(Node::Paragraph(std::mem::take(inline)));
+    }
+}
+
+/// Returns whether an element has the given class.
+fn has_class(element: &ElementRef, class: &str) -> bool {
+    element.value().classes().any(|x| x == class)
+}
+
+/// Parses the children of an element.
+fn parse_children(element: &ElementRef) -> Vec<Node> {
+    element.children().flat_map(parse_node).collect()
+}
+
+/// Parses a node into zero or more nodes. Unknown elements are replaced by
+/// their children so that their text is preserved.
+fn parse_node(node: ego_tree::NodeRef<scraper::Node>) -> Vec<Node> {
+    if let scraper::Node::Text(text) = node.value() {
+        // Newlines in the page source are layout, since line breaks within a
+        // post are rendered as <br>.
+        if text.contains('\n') && text.trim().is_empty() {
+            return Vec::new();
+        }
+        let text: String = text.chars().filter(|&c| c != '\n' && c != '\r').collect();
+        return vec![Node::Text(text)];
+    }
+    let Some(element) = ElementRef::wrap(node) else {
+        return Vec::new();
+    };
+
+    let node = match element.value().name() {
+        "br" => Node::LineBreak,
+        "b" | "strong" => Node::Bold(parse_children(&element)),
+        "i" | "em" => Node::Italic(parse_children(&element)),
+        "u" => Node::Underline(parse_children(&element)),
+        "s" | "del" | "strike" => Node::Strikethrough(parse_children(&element)),
+        "sup" => Node::Superscript(parse_children(&element)),
+        "sub" => Node::Subscript(parse_children(&element)),
+        "tt" => Node::Fixed(parse_children(&element)),
+        "span" if has_class(&element, "bbc-spoiler") => Node::Spoiler(parse_children(&element)),
+        "div" if has_class(&element, "bbc-block") => parse_block(&element),
+        "blockquote" => Node::Quote {
+            author: None,
+            post_id: None,
+            children: parse_children(&element),
+        },
+        "pre" => Node::Code {
+            kind: CodeKind::Pre,
+            text: element.text().collect(),
+        },
+        "img" => parse_image(&element),
+        "a" => {
+            let children = parse_children(&element);
+            let url = element.value().attr("href").unwrap_or_default().to_owned();
+            // Thumbnailed images are wrapped in a link to themselves.
+            match children.as_slice() {
+                [Node::Image {
+                    url: image_url,
+                    thumbnail: true,
+                }] if *image_url == url => children.into_iter().next().unwrap(),
+                _ => Node::Link { url, children },
+            }
+        }
+        "iframe" | "video" | "embed" => parse_video(&element),
+        "ul" | "ol" => {
+            let selector = scraper::Selector::parse(r#"li"#).expect("BUG: illegal selector");
+            Node::List {
+                ordered: element.value().name() == "ol",
+                items: element
+                    .children()
+                    .filter_map(ElementRef::wrap)
+                    .filter(|x| selector.matches(x))
+                    .map(|x| parse_children(&x))
+                    .collect(),
+            }
+        }
+        "script" | "style" => return Vec::new(),
+        _ => return parse_children(&element),
+    };
+    vec![node]
+}
+
+/// Parses a quote or code block.
+fn parse_block(element: &ElementRef) -> Node {
+    let heading = scraper::Selector::parse(r#"h4, h5"#).expect("BUG: illegal selector");
+    let heading = element.select(&heading).next();
+
+    let code = scraper::Selector::parse(r#"pre"#).expect("BUG: illegal selector");
+    if let Some(code) = element.select(&code).next() {
+        let kind = if has_class(element, "php") {
+            CodeKind::Php
+        } else if has_class(element, "pre") {
+            CodeKind::Pre
+        } else {
+            CodeKind::Code
+        };
+        return Node::Code {
+            kind,
+            text: code.text().collect(),
+        };
+    }
+
+    let author = heading
+        .map(|x| scrape::trimmed_text(&x))
+        .and_then(|x| x.strip_suffix(" posted:").map(str::to_owned));
+    let link = scraper::Selector::parse(r#"a[href*="postid="]"#).expect("BUG: illegal selector");
+    let post_id = heading
+        .and_then(|x| x.select(&link).next())
+        .and_then(|x| x.value().attr("href"))
+        .and_then(|href| scrape::query_param(href, "postid"));
+    let quote = scraper::Selector::parse(r#"blockquote"#).expect("BUG: illegal selector");
+    let children = element
+        .select(&quote)
+        .next()
+        .map(|x| parse_children(&x))
+        .unwrap_or_default();
+    Node::Quote {
+        author,
+        post_id,
+        children,
+    }
+}
+
+/// Parses an image, which may be a smilie.
+fn parse_image(element: &ElementRef) -> Node {
+    let url = element.value().attr("src").unwrap_or_default().to_owned();
+    let title = element.value().attr("title").unwrap_or_default();
+    if title.starts_with(':') || url.contains("/smilies/") {
+        let code = if title.is_empty() {
+            element.value().attr("alt").unwrap_or_default()
+        } else {
+            title
+        };
+        return Node::Smilie {
+            code: code.to_owned(),
+            url,
+        };
+    }
+    Node::Image {
+        url,
+        thumbnail: has_class(element, "timg"),
+    }
+}
+
+/// Parses an embedded video.
+fn parse_video(element: &ElementRef) -> Node {
+    let source = scraper::Selector::parse(r#"source"#).expect("BUG: illegal selector");
+    let url = element
+        .value()
+        .attr("src")
+        .or_else(|| {
+            element
+                .select(&source)
+                .next()
+                .and_then(|x| x.value().attr("src"))
+        })
+        .unwrap_or_default();
+    Node::Video {
+        url: url.to_owned(),
+    }
+}
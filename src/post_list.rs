@@ -1,17 +1,65 @@
-use crate::Error;
+use crate::{
+    post_body::{EditNotice, PostBody},
+    scrape,
+    timestamp::{Date, Timestamp},
+    Error,
+};
+use url::Url;
 
 /// Represents a specific post within a thread.
 #[derive(Debug)]
 pub struct Post {
     pub id: String,
     pub index: i64,
+
+    /// The author's user ID, or None if the page does not show it.
+    pub author_id: Option<String>,
+
     pub author_username: String,
-    pub author_registration_date: String,
-    pub post_date: String,
+    pub author_registration_date: Date,
+
+    /// The inner HTML of the author's custom title, which may contain images
+    /// and line breaks.
+    pub author_title: String,
+
+    /// The URL of the author's avatar, or None if the author has no avatar.
+    pub author_avatar_url: Option<String>,
+
+    pub author_platinum: bool,
+    pub author_moderator: bool,
+    pub author_admin: bool,
+
+    pub post_date: Timestamp,
     pub post_body: String,
+
+    /// The notice shown when the post was edited, if any.
+    pub edited: Option<EditNotice>,
+
+    /// Whether the author is on the current user's ignore list, in which case
+    /// the post body is hidden by default.
+    pub ignored: bool,
+
+    /// Whether the current user has already seen the post.
+    pub seen: bool,
 }
 
 impl Post {
+    /// Returns a link to the post, which works regardless of which page the
+    /// post is on.
+    pub fn url(&self, base: &Url) -> Result<Url, Error> {
+        let mut url = base.join("showthread.php")?;
+        url.query_pairs_mut()
+            .append_pair("goto", "post")
+            .append_pair("postid", &self.id);
+        url.set_fragment(Some(&format!("post{}", self.id)));
+        Ok(url)
+    }
+
+    /// Parses the post body into a tree of content.
+    pub fn parse_body(&self) -> PostBody {
+        PostBody::parse(&self.post_body)
+    }
+
     /// Parses all posts on a thread page.
     pub fn parse_list(document: &str) -> Result<Vec<Post>, Error> {
         let mut posts = Vec::new();
@@ -19,78 +67,126 @@ impl Post {
         let selector = scraper::Selector::parse(r#"table.post"#).expect("BUG: illegal selector");
 
         for post in document.select(&selector) {
-            let parsing_error = Error::PostParsingError(post.inner_html());
+            let parsing_error = |reason: &str| Error::PostParsingError(reason.to_owned());
             let selector =
                 scraper::Selector::parse(r#"table.post>tbody>tr"#).expect("BUG: illegal selector");
             let mut post_body = post.select(&selector);
 
             let Some(author_and_body) = post_body.next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing author row"));
             };
 
             let Some(date_and_links) = post_body.next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing date row"));
             };
 
             let Some(id) = post.value().attr("id") else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing post ID"));
             };
 
             if !id.starts_with("post") {
-                return Err(parsing_error);
+                return Err(parsing_error("malformed post ID"));
             }
             let id = id["post".len()..].to_owned();
 
             let Some(index) = post.value().attr("data-idx") else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing post index"));
             };
             let Ok(index) = index.parse() else {
-                return Err(parsing_error);
+                return Err(parsing_error("malformed post index"));
             };
 
             let selector =
                 scraper::Selector::parse(r#"dl.userinfo>dt"#).expect("BUG: illegal selector");
             let Some(author_username) = author_and_body.select(&selector).next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing author"));
             };
 
+            let author_classes: Vec<_> = author_username.value().classes().collect();
+            let author_platinum = author_classes.contains(&"platinum");
+            let author_moderator = author_classes
+                .iter()
+                .any(|x| *x == "role-mod" || *x == "role-supermod");
+            let author_admin = author_classes.contains(&"role-admin");
+
             let mut author_username = author_username.text();
             let Some(author_username) = author_username.next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing author username"));
             };
             let author_username = author_username.to_owned();
 
             let selector = scraper::Selector::parse(r#"dl.userinfo>dd.registered"#)
                 .expect("BUG: illegal selector");
             let Some(author_registration_date) = author_and_body.select(&selector).next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing registration date"));
             };
-            let author_registration_date = author_registration_date.inner_html();
+            let author_registration_date = Date::parse(&author_registration_date.inner_html());
+
+            let selector =
+                scraper::Selector::parse(r#"td.userinfo"#).expect("BUG: illegal selector");
+            let author_id = author_and_body.select(&selector).next().and_then(|x| {
+                x.value()
+                    .classes()
+                    .find_map(|class| class.strip_prefix("userid-"))
+                    .map(str::to_owned)
+            });
+
+            let selector =
+                scraper::Selector::parse(r#"dl.userinfo>dd.title"#).expect("BUG: illegal selector");
+            let author_title = author_and_body.select(&selector).next();
+            let selector = scraper::Selector::parse(r#"img"#).expect("BUG: illegal selector");
+            let author_avatar_url = author_title
+                .and_then(|x| x.select(&selector).next())
+                .and_then(|x| x.value().attr("src"))
+                .map(str::to_owned);
+            let author_title = author_title
+                .map(|x| x.inner_html().trim().to_owned())
+                .unwrap_or_default();
 
             let selector =
                 scraper::Selector::parse(r#"tr>td.postdate"#).expect("BUG: illegal selector");
             let Some(post_date) = date_and_links.select(&selector).next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing post date"));
             };
             let Some(post_date) = post_date.text().last() else {
-                return Err(parsing_error);
+                return Err(parsing_error("empty post date"));
             };
-            let post_date = post_date.trim().to_owned();
+            let post_date = Timestamp::parse(post_date);
 
             let selector =
                 scraper::Selector::parse(r#"td.postbody"#).expect("BUG: illegal selector");
             let Some(post_body) = author_and_body.select(&selector).next() else {
-                return Err(parsing_error);
+                return Err(parsing_error("missing post body"));
             };
+            let selector =
+                scraper::Selector::parse(r#"p.editedby"#).expect("BUG: illegal selector");
+            let edited = post_body
+                .select(&selector)
+                .next()
+                .map(|x| EditNotice::parse(&scrape::trimmed_text(&x)));
             let post_body = post_body.inner_html();
 
+            let ignored = post.value().classes().any(|x| x == "ignored");
+            let selector =
+                scraper::Selector::parse(r#"tr.seen1, tr.seen2"#).expect("BUG: illegal selector");
+            let seen = post.select(&selector).next().is_some();
+
             posts.push(Post {
                 id,
                 index,
+                author_id,
                 author_username,
                 author_registration_date,
+                author_title,
+                author_avatar_url,
+                author_platinum,
+                author_moderator,
+                author_admin,
                 post_date,
                 post_body,
+                edited,
+                ignored,
+                seen,
             });
         }
 
@@ -0,0 +1,91 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Limits how often a client sends requests, so that it does not overload
+/// the forums. Requests beyond the limit wait until they are allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    burst: u32,
+    interval: Duration,
+}
+
+impl RateLimit {
+    /// Allows one request per interval on average. Up to burst requests may
+    /// be sent back to back after the client has been idle.
+    pub fn new(burst: u32, interval: Duration) -> RateLimit {
+        RateLimit {
+            burst: burst.max(1),
+            interval,
+        }
+    }
+
+    /// Allows requests to be sent as fast as possible.
+    pub fn unlimited() -> RateLimit {
+        RateLimit::new(1, Duration::ZERO)
+    }
+}
+
+impl Default for RateLimit {
+    /// Allows two requests per second, in bursts of up to five.
+    fn default() -> Self {
+        RateLimit::new(5, Duration::from_millis(500))
+    }
+}
+
+/// Hands out permission to send requests according to a rate limit. Shared
+/// between clones of a client so that they are limited together.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    limit: RateLimit,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    /// The number of requests that may be sent immediately. Negative when
+    /// requests are queued waiting for tokens.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    pub(crate) fn new(limit: RateLimit) -> TokenBucket {
+        TokenBucket {
+            limit,
+            state: Mutex::new(BucketState {
+                tokens: limit.burst as f64,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub(crate) async fn acquire(&self) {
+        if self.limit.interval.is_zero() {
+            return;
+        }
+
+        // Take a token even if none are available, so that waiting requests
+        // are let through in the order they arrived.
+        let wait = {
+            let mut state = self.state.lock().expect("BUG: lock failed");
+            let now = Instant::now();
+            let refilled =
+                now.duration_since(state.updated).as_secs_f64() / self.limit.interval.as_secs_f64();
+            state.tokens = (state.tokens + refilled).min(self.limit.burst as f64);
+            state.updated = now;
+            state.tokens -= 1.0;
+            if state.tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                self.limit.interval.mul_f64(-state.tokens)
+            }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
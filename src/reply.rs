@@ -1,4 +1,4 @@
-use crate::Error;
+use crate::{bbcode::Validator, scrape, Error};
 
 /// Reply parameters read from the server.
 pub struct ReplyParams {
@@ -6,6 +6,9 @@ pub struct ReplyParams {
     threadid: String,
     formkey: String,
     form_cookie: String,
+
+    /// The message prefilled by the server, such as quoted posts.
+    message: String,
 }
 
 impl ReplyParams {
@@ -13,57 +16,45 @@ impl ReplyParams {
     /// sent back in the reply request.
     pub fn parse(document: &str) -> Result<ReplyParams, Error> {
         let document = scraper::Html::parse_document(document);
-
-        let selector =
-            scraper::Selector::parse(r#"form[name="vbform"]"#).expect("BUG: illegal selector");
-        let Some(form) = document.select(&selector).next() else {
+        let Some(form) = scrape::form(&document, "vbform") else {
             return Err(Error::ReplyParsingError);
         };
-
-        let selector =
-            scraper::Selector::parse(r#"input[name="action"]"#).expect("BUG: illegal selector");
-        let Some(action) = form.select(&selector).next() else {
+        let Some(action) = scrape::input_value(&form, "action") else {
             return Err(Error::ReplyParsingError);
         };
-        let Some(action) = action.value().attr("value") else {
+        let Some(threadid) = scrape::input_value(&form, "threadid") else {
             return Err(Error::ReplyParsingError);
         };
-
-        let selector =
-            scraper::Selector::parse(r#"input[name="threadid"]"#).expect("BUG: illegal selector");
-        let Some(threadid) = form.select(&selector).next() else {
+        let Some(formkey) = scrape::input_value(&form, "formkey") else {
             return Err(Error::ReplyParsingError);
         };
-        let Some(threadid) = threadid.value().attr("value") else {
+        let Some(form_cookie) = scrape::input_value(&form, "form_cookie") else {
             return Err(Error::ReplyParsingError);
         };
 
         let selector =
-            scraper::Selector::parse(r#"input[name="formkey"]"#).expect("BUG: illegal selector");
-        let Some(formkey) = form.select(&selector).next() else {
-            return Err(Error::ReplyParsingError);
-        };
-        let Some(formkey) = formkey.value().attr("value") else {
-            return Err(Error::ReplyParsingError);
-        };
-
-        let selector = scraper::Selector::parse(r#"input[name="form_cookie"]"#)
-            .expect("BUG: illegal selector");
-        let Some(form_cookie) = form.select(&selector).next() else {
-            return Err(Error::ReplyParsingError);
-        };
-        let Some(form_cookie) = form_cookie.value().attr("value") else {
-            return Err(Error::ReplyParsingError);
-        };
+            scraper::Selector::parse(r#"textarea[name="message"]"#).expect("BUG: illegal selector");
+        let message = form
+            .select(&selector)
+            .next()
+            .map(|x| x.text().collect())
+            .unwrap_or_default();
 
         Ok(ReplyParams {
-            action: action.to_owned(),
-            threadid: threadid.to_owned(),
-            formkey: formkey.to_owned(),
-            form_cookie: form_cookie.to_owned(),
+            action,
+            threadid,
+            formkey,
+            form_cookie,
+            message,
         })
     }
 
+    /// Returns the BBCode message that the server prefilled in the reply form.
+    /// This is empty unless the form was requested to quote posts.
+    pub fn prefilled_message(&self) -> &str {
+        &self.message
+    }
+
     /// Consumes the builder and a reply, returning a form corresponding to the
     /// post reply payload.
     pub fn into_form(self, reply: Reply) -> Result<reqwest::multipart::Form, Error> {
@@ -88,9 +79,10 @@ impl ReplyParams {
 
 /// Represents a message that the user will reply with.
 pub struct Reply {
-    message: String,
-    bookmark: bool,
-    attachment: Option<(String, Vec<u8>)>,
+    pub(crate) message: String,
+    pub(crate) bookmark: bool,
+    pub(crate) attachment: Option<(String, Vec<u8>)>,
+    validator: Validator,
 }
 
 impl Reply {
@@ -100,6 +92,18 @@ impl Reply {
             message: message.to_owned(),
             bookmark: true,
             attachment: None,
+            validator: Validator::new(),
+        }
+    }
+
+    /// Creates a reply that quotes posts before the given BBCode message. The
+    /// quote should be fetched with Client::fetch_quote.
+    pub fn quoting(quote: &str, message: &str) -> Reply {
+        let quote = quote.trim_end();
+        if quote.is_empty() {
+            Reply::new(message)
+        } else {
+            Reply::new(&format!("{quote}\n\n{message}"))
         }
     }
 
@@ -116,4 +120,76 @@ impl Reply {
         self.attachment = Some((filename.to_owned(), contents.to_owned()));
         self
     }
+
+    /// Sets the maximum number of images that the message may contain.
+    /// Defaults to no limit.
+    pub fn with_max_images(mut self, max_images: usize) -> Self {
+        self.validator = self.validator.with_max_images(max_images);
+        self
+    }
+
+    /// Checks the message for unbalanced tags and too many images. Returns
+    /// InvalidBBCode listing every problem found.
+    pub fn validate(&self) -> Result<(), Error> {
+        let issues = self.validator.validate(&self.message);
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidBBCode(issues))
+        }
+    }
+}
+
+/// Identifies a reply after it was posted.
+#[derive(Debug, Clone)]
+pub struct PostedReply {
+    pub post_id: String,
+    pub thread_id: String,
+
+    /// The page of the thread that the post is on, starting from 1. None if
+    /// the post could not be found in the thread after posting.
+    pub page: Option<usize>,
+
+    /// The index of the post within the thread, starting from 1. None if the
+    /// post could not be found in the thread after posting.
+    pub index: Option<i64>,
+}
+
+/// Returns the ID of the post that a reply created, read from the URL that
+/// posting redirected to or from the refresh on the page shown after posting.
+/// Links elsewhere on the page are not trusted, since a rejected reply is
+/// shown again with its form, which may link to other posts.
+pub(crate) fn parse_posted_id(url: &url::Url, document: &str) -> Option<String> {
+    if let Some(post_id) = scrape::query_param(url.as_str(), "postid") {
+        return Some(post_id);
+    }
+
+    let document = scraper::Html::parse_document(document);
+    let selector = scraper::Selector::parse(r#"meta[http-equiv]"#).expect("BUG: illegal selector");
+    let post_id = document
+        .select(&selector)
+        .filter(|x| {
+            x.value()
+                .attr("http-equiv")
+                .is_some_and(|x| x.eq_ignore_ascii_case("refresh"))
+        })
+        .filter_map(|x| x.value().attr("content"))
+        .find_map(|content| {
+            let (_, href) = content.split_once('=')?;
+            scrape::query_param(href, "postid")
+        });
+    post_id
+}
+
+/// Returns the reasons that the server gave for rejecting a reply, if any.
+pub(crate) fn parse_rejection(document: &str) -> Vec<String> {
+    let document = scraper::Html::parse_document(document);
+    let selector =
+        scraper::Selector::parse(r#".errors li, #errors li"#).expect("BUG: illegal selector");
+    let reasons = document
+        .select(&selector)
+        .map(|x| scrape::trimmed_text(&x))
+        .filter(|x| !x.is_empty())
+        .collect();
+    reasons
 }
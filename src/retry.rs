@@ -0,0 +1,84 @@
+use crate::Error;
+use std::time::Duration;
+
+/// Describes how a client retries requests that fail for reasons that are
+/// likely to be temporary, such as timeouts, server errors and database
+/// errors. Requests that change state on the server, such as posting, are
+/// only retried if they never reached the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates a policy that retries up to three times, waiting half a second
+    /// before the first retry and doubling the wait for each retry after.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Creates a policy that never retries.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new().with_max_retries(0)
+    }
+
+    /// Sets the number of times a request is retried after its first
+    /// attempt. Defaults to 3.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the wait before the first retry. Each later retry waits twice as
+    /// long as the one before it. Defaults to 500 milliseconds.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Sets the longest wait between retries. Defaults to 30 seconds.
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Returns how long to wait before retrying after the given number of
+    /// failed attempts, or None if the request should not be retried.
+    pub(crate) fn backoff(&self, attempts: u32, idempotent: bool, err: &Error) -> Option<Duration> {
+        if attempts > self.max_retries || !is_transient(err, idempotent) {
+            return None;
+        }
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempts - 1));
+        Some(backoff.min(self.max_backoff))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new()
+    }
+}
+
+/// Returns whether an error is likely to go away if the request is sent
+/// again.
+fn is_transient(err: &Error, idempotent: bool) -> bool {
+    match err {
+        // The request never reached the server, so it is always safe to send
+        // again.
+        Error::ReqwestError(err) if err.is_connect() => true,
+        _ if !idempotent => false,
+        Error::ReqwestError(err) => {
+            err.is_timeout() || err.status().is_some_and(|x| x.is_server_error())
+        }
+        Error::DatabaseError => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,44 @@
+/// Returns the value of a query parameter in a link, which may be relative.
+pub(crate) fn query_param(href: &str, name: &str) -> Option<String> {
+    let base = url::Url::parse("https://localhost/").expect("BUG: illegal base URL");
+    let url = base.join(href).ok()?;
+    let value = url
+        .query_pairs()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned());
+    value
+}
+
+/// Returns the text within an element with surrounding whitespace removed.
+pub(crate) fn trimmed_text(element: &scraper::ElementRef) -> String {
+    element.text().collect::<String>().trim().to_owned()
+}
+
+/// Returns the form with the given name.
+pub(crate) fn form<'a>(document: &'a scraper::Html, name: &str) -> Option<scraper::ElementRef<'a>> {
+    let selector = scraper::Selector::parse(&format!(r#"form[name="{name}"]"#))
+        .expect("BUG: illegal selector");
+    let form = document.select(&selector).next();
+    form
+}
+
+/// Returns the value of the named input within a form.
+pub(crate) fn input_value(form: &scraper::ElementRef, name: &str) -> Option<String> {
+    let selector = scraper::Selector::parse(&format!(r#"input[name="{name}"]"#))
+        .expect("BUG: illegal selector");
+    let input = form.select(&selector).next()?;
+    input.value().attr("value").map(str::to_owned)
+}
+
+/// Returns the names and values of all hidden inputs within a form.
+pub(crate) fn hidden_inputs(form: &scraper::ElementRef) -> Vec<(String, String)> {
+    let selector =
+        scraper::Selector::parse(r#"input[type="hidden"]"#).expect("BUG: illegal selector");
+    form.select(&selector)
+        .filter_map(|input| {
+            let name = input.value().attr("name")?;
+            let value = input.value().attr("value").unwrap_or_default();
+            Some((name.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
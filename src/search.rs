@@ -0,0 +1,245 @@
+use crate::{pagination::Pagination, scrape, timestamp::Timestamp, Error};
+use chrono::NaiveDate;
+
+/// Describes which posts to search for.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    keywords: String,
+    author: Option<String>,
+    forum_ids: Vec<String>,
+    thread_id: Option<String>,
+    since: Option<NaiveDate>,
+    before: Option<NaiveDate>,
+    titles_only: bool,
+}
+
+impl SearchQuery {
+    /// Creates a query for posts containing the given keywords. Keywords may
+    /// be empty if other filters are set.
+    pub fn new(keywords: &str) -> SearchQuery {
+        SearchQuery {
+            keywords: keywords.to_owned(),
+            author: None,
+            forum_ids: Vec::new(),
+            thread_id: None,
+            since: None,
+            before: None,
+            titles_only: false,
+        }
+    }
+
+    /// Restricts results to posts by the given username, overriding any
+    /// existing author. Defaults to any author.
+    pub fn with_author(mut self, username: &str) -> Self {
+        self.author = Some(username.to_owned());
+        self
+    }
+
+    /// Adds a forum to search within. Defaults to searching all forums.
+    pub fn with_forum(mut self, forum_id: &str) -> Self {
+        self.forum_ids.push(forum_id.to_owned());
+        self
+    }
+
+    /// Restricts results to a single thread, overriding any existing thread.
+    /// Defaults to any thread.
+    pub fn with_thread(mut self, thread_id: &str) -> Self {
+        self.thread_id = Some(thread_id.to_owned());
+        self
+    }
+
+    /// Restricts results to posts made on or after a date. Defaults to no
+    /// lower bound.
+    pub fn with_since(mut self, date: NaiveDate) -> Self {
+        self.since = Some(date);
+        self
+    }
+
+    /// Restricts results to posts made before a date. Defaults to no upper
+    /// bound.
+    pub fn with_before(mut self, date: NaiveDate) -> Self {
+        self.before = Some(date);
+        self
+    }
+
+    /// Sets whether keywords only match thread titles. Defaults to false.
+    pub fn with_titles_only(mut self, titles_only: bool) -> Self {
+        self.titles_only = titles_only;
+        self
+    }
+
+    /// Returns the fields of the search request.
+    pub(crate) fn into_form(self) -> Vec<(String, String)> {
+        let mut terms = Vec::new();
+        if !self.keywords.is_empty() {
+            if self.titles_only {
+                terms.push(format!("intitle:{}", quote(&self.keywords)));
+            } else {
+                terms.push(self.keywords);
+            }
+        }
+        if let Some(author) = self.author {
+            terms.push(format!("username:{}", quote(&author)));
+        }
+        if let Some(thread_id) = self.thread_id {
+            terms.push(format!("threadid:{thread_id}"));
+        }
+        if let Some(since) = self.since {
+            terms.push(date_term("since", since));
+        }
+        if let Some(before) = self.before {
+            terms.push(date_term("before", before));
+        }
+
+        let mut form = vec![
+            (String::from("action"), String::from("query")),
+            (String::from("q"), terms.join(" ")),
+        ];
+        for forum_id in self.forum_ids {
+            form.push((String::from("forums[]"), forum_id));
+        }
+        form
+    }
+}
+
+/// Quotes a search term if it contains whitespace.
+fn quote(term: &str) -> String {
+    if term.contains(char::is_whitespace) {
+        format!(r#""{term}""#)
+    } else {
+        term.to_owned()
+    }
+}
+
+/// Formats a search term that filters by date, such as "since:2023-06-18".
+fn date_term(name: &str, date: NaiveDate) -> String {
+    format!("{name}:{}", date.format("%Y-%m-%d"))
+}
+
+/// Represents a single post matching a search.
+#[derive(Debug)]
+pub struct SearchResult {
+    pub post_id: String,
+    pub thread_id: String,
+    pub thread_title: String,
+    pub forum_id: String,
+    pub author_username: String,
+    pub post_date: Timestamp,
+
+    /// The inner HTML of the matching excerpt, with matches highlighted.
+    pub snippet: String,
+}
+
+/// Represents one page of search results.
+#[derive(Debug)]
+pub struct SearchResults {
+    /// Identifies the search on the server, so that other pages of results
+    /// can be fetched.
+    pub query_id: String,
+
+    pub results: Vec<SearchResult>,
+    pub pagination: Pagination,
+}
+
+impl SearchResults {
+    /// Parses a page of search results for the given query ID.
+    pub fn parse(document: &str, query_id: &str) -> Result<SearchResults, Error> {
+        Ok(SearchResults {
+            query_id: query_id.to_owned(),
+            results: SearchResult::parse_list(document)?,
+            pagination: Pagination::parse(document),
+        })
+    }
+}
+
+/// Returns the query ID linked from a page of search results, if any.
+pub(crate) fn parse_query_id(document: &str) -> Option<String> {
+    let document = scraper::Html::parse_document(document);
+    let selector = scraper::Selector::parse(r#"a[href*="qid="]"#).expect("BUG: illegal selector");
+    let query_id = document
+        .select(&selector)
+        .find_map(|x| scrape::query_param(x.value().attr("href")?, "qid"));
+    query_id
+}
+
+impl SearchResult {
+    /// Parses all results on a page of search results.
+    pub fn parse_list(document: &str) -> Result<Vec<SearchResult>, Error> {
+        let mut results = Vec::new();
+        let document = scraper::Html::parse_document(document);
+        let selector =
+            scraper::Selector::parse(r#"div.search_result"#).expect("BUG: illegal selector");
+
+        for result in document.select(&selector) {
+            let parsing_error = |reason: &str| Error::SearchParsingError(reason.to_owned());
+
+            let selector =
+                scraper::Selector::parse(r#"a.threadtitle"#).expect("BUG: illegal selector");
+            let Some(thread) = result.select(&selector).next() else {
+                return Err(parsing_error("missing thread link"));
+            };
+            let Some(thread_id) = thread
+                .value()
+                .attr("href")
+                .and_then(|href| scrape::query_param(href, "threadid"))
+            else {
+                return Err(parsing_error("missing thread ID"));
+            };
+            let thread_title = scrape::trimmed_text(&thread);
+
+            let selector =
+                scraper::Selector::parse(r#"a[href*="postid="]"#).expect("BUG: illegal selector");
+            let Some(post_id) = result
+                .select(&selector)
+                .next()
+                .and_then(|x| x.value().attr("href"))
+                .and_then(|href| scrape::query_param(href, "postid"))
+            else {
+                return Err(parsing_error("missing post ID"));
+            };
+
+            let selector =
+                scraper::Selector::parse(r#"a.forumtitle"#).expect("BUG: illegal selector");
+            let Some(forum_id) = result
+                .select(&selector)
+                .next()
+                .and_then(|x| x.value().attr("href"))
+                .and_then(|href| scrape::query_param(href, "forumid"))
+            else {
+                return Err(parsing_error("missing forum ID"));
+            };
+
+            let selector =
+                scraper::Selector::parse(r#"a.username"#).expect("BUG: illegal selector");
+            let Some(author_username) = result.select(&selector).next() else {
+                return Err(parsing_error("missing author"));
+            };
+            let author_username = scrape::trimmed_text(&author_username);
+
+            let selector = scraper::Selector::parse(r#"span.date"#).expect("BUG: illegal selector");
+            let Some(post_date) = result.select(&selector).next() else {
+                return Err(parsing_error("missing post date"));
+            };
+            let post_date = Timestamp::parse(&scrape::trimmed_text(&post_date));
+
+            let selector = scraper::Selector::parse(r#"div.blurb"#).expect("BUG: illegal selector");
+            let snippet = result
+                .select(&selector)
+                .next()
+                .map(|x| x.inner_html().trim().to_owned())
+                .unwrap_or_default();
+
+            results.push(SearchResult {
+                post_id,
+                thread_id,
+                thread_title,
+                forum_id,
+                author_username,
+                post_date,
+                snippet,
+            });
+        }
+
+        Ok(results)
+    }
+}
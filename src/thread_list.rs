@@ -1,19 +1,190 @@
-use crate::Error;
+use crate::{
+    new_thread::PostIcon,
+    pagination::Pagination,
+    scrape,
+    timestamp::Timestamp,
+    Error,
+};
 
 /// Represents a single thread within a list of threads.
 #[derive(Debug)]
 pub struct Thread {
     pub id: String,
     pub title: String,
+    pub author_id: String,
     pub author_username: String,
     pub replies: i64,
     pub views: i64,
-    pub last_post_date: String,
+
+    /// The number of pages in the thread.
+    pub pages: usize,
+
+    pub last_post_date: Timestamp,
     pub last_post_username: String,
 
+    /// A link to the most recent post.
+    pub last_post_url: String,
+
     /// Zero if there are no unread posts in this thread. Otherwise, the
     /// number of unread posts.
     pub unread: i64,
+
+    /// The star shown next to the thread, or None if the thread is not
+    /// bookmarked.
+    pub star: Option<BookmarkStar>,
+
+    /// The thread tag chosen by the author, or None if there is no tag.
+    pub icon: Option<PostIcon>,
+
+    /// The secondary tag that some forums use to classify threads, such as
+    /// "ask" or "tell".
+    pub secondary_icon: Option<PostIcon>,
+
+    /// The thread's vote rating, or None if it has too few votes to be shown.
+    pub rating: Option<Rating>,
+
+    pub sticky: bool,
+    pub announcement: bool,
+    pub closed: bool,
+}
+
+/// Represents the votes cast on a thread.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rating {
+    pub votes: i64,
+
+    /// The average vote, between 1 and 5.
+    pub average: f64,
+}
+
+impl Rating {
+    /// Parses a rating description, such as "114 votes - 4.82 average".
+    pub fn parse(description: &str) -> Option<Rating> {
+        let (votes, average) = description.split_once(" - ")?;
+        let votes = votes.trim().strip_suffix("votes")?.trim().parse().ok()?;
+        let average = average
+            .trim()
+            .strip_suffix("average")?
+            .trim()
+            .parse()
+            .ok()?;
+        Some(Rating { votes, average })
+    }
+}
+
+/// Represents the color of the star that categorizes a bookmarked thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookmarkStar {
+    Orange,
+    Red,
+    Yellow,
+    Cyan,
+    Green,
+    Purple,
+}
+
+impl BookmarkStar {
+    /// All stars, in the order that the server cycles through them.
+    pub const ALL: [BookmarkStar; 6] = [
+        BookmarkStar::Orange,
+        BookmarkStar::Red,
+        BookmarkStar::Yellow,
+        BookmarkStar::Cyan,
+        BookmarkStar::Green,
+        BookmarkStar::Purple,
+    ];
+
+    /// Returns the star with the given category ID used by the server.
+    pub fn from_category(category: i64) -> Option<BookmarkStar> {
+        usize::try_from(category)
+            .ok()
+            .and_then(|category| BookmarkStar::ALL.get(category))
+            .copied()
+    }
+
+    /// Returns the category ID used by the server.
+    pub fn category(&self) -> i64 {
+        BookmarkStar::ALL
+            .iter()
+            .position(|x| x == self)
+            .expect("BUG: star missing from ALL") as i64
+    }
+}
+
+/// Represents one page of a list of threads.
+#[derive(Debug)]
+pub struct ThreadList {
+    pub threads: Vec<Thread>,
+    pub pagination: Pagination,
+}
+
+/// Selects the column that a forum's threads are sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ThreadSortField {
+    /// Sorts by the time of the most recent post.
+    #[default]
+    LastPost,
+
+    /// Sorts by thread title.
+    Title,
+
+    /// Sorts by the number of replies.
+    Replies,
+
+    /// Sorts by the number of views.
+    Views,
+
+    /// Sorts by the username of the thread author.
+    Author,
+
+    /// Sorts by the average vote.
+    Rating,
+}
+
+/// Selects the direction that a forum's threads are sorted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    Ascending,
+
+    #[default]
+    Descending,
+}
+
+/// Determines the order of threads within a forum. Defaults to the most
+/// recently active threads first, which matches the website.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ThreadSort {
+    pub field: ThreadSortField,
+    pub order: SortOrder,
+}
+
+impl ThreadSort {
+    /// Returns the query parameters that select this sort order.
+    pub(crate) fn query(&self) -> [(&'static str, &'static str); 2] {
+        let field = match self.field {
+            ThreadSortField::LastPost => "lastpost",
+            ThreadSortField::Title => "title",
+            ThreadSortField::Replies => "replycount",
+            ThreadSortField::Views => "views",
+            ThreadSortField::Author => "postusername",
+            ThreadSortField::Rating => "voteavg",
+        };
+        let order = match self.order {
+            SortOrder::Ascending => "asc",
+            SortOrder::Descending => "desc",
+        };
+        [("sortfield", field), ("sortorder", order)]
+    }
+}
+
+impl ThreadList {
+    /// Parses a page of threads along with its pager.
+    pub fn parse(document: &str) -> Result<ThreadList, Error> {
+        Ok(ThreadList {
+            threads: Thread::parse_list(document)?,
+            pagination: Pagination::parse(document),
+        })
+    }
 }
 
 impl Thread {
@@ -25,59 +196,71 @@ impl Thread {
             scraper::Selector::parse(r#"tbody>tr.thread"#).expect("BUG: illegal selector");
 
         for thread in document.select(&selector) {
-            let parsing_error = Error::ThreadParsingError(thread.inner_html());
+            let parsing_error = |reason: &str| Error::ThreadParsingError(reason.to_owned());
             let Some(id) = thread.value().attr("id") else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing thread ID"));
                 };
             if !id.starts_with("thread") {
-                return Err(parsing_error);
+                return Err(parsing_error("malformed thread ID"));
             }
             let thread_id = id["thread".len()..].to_owned();
             let selector =
                 scraper::Selector::parse(r#"a.thread_title"#).expect("BUG: illegal selector");
             let Some(title) = thread.select(&selector).next() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing thread title"));
                 };
             let title = title.inner_html();
 
             let selector =
                 scraper::Selector::parse(r#"td.author>a"#).expect("BUG: illegal selector");
             let Some(author) = thread.select(&selector).next() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing author"));
                 };
             let author_username = author.inner_html();
+            let Some(author_id) = author
+                .value()
+                .attr("href")
+                .and_then(|href| scrape::query_param(href, "userid"))
+            else {
+                return Err(parsing_error("missing author ID"));
+            };
 
             let selector =
                 scraper::Selector::parse(r#"td.replies>a"#).expect("BUG: illegal selector");
             let Some(replies) = thread.select(&selector).next() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing reply count"));
                 };
             let replies = replies.inner_html();
             let Ok(replies) = replies.parse() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("malformed reply count"));
                 };
 
             let selector = scraper::Selector::parse(r#"td.views"#).expect("BUG: illegal selector");
             let Some(views) = thread.select(&selector).next() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing view count"));
                 };
             let views = views.inner_html();
             let Ok(views) = views.parse() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("malformed view count"));
                 };
 
             let selector =
                 scraper::Selector::parse(r#"td.lastpost>div.date"#).expect("BUG: illegal selector");
             let Some(last_post_date) = thread.select(&selector).next() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing last post date"));
                 };
-            let last_post_date = last_post_date.inner_html();
+            let last_post_date = Timestamp::parse(&last_post_date.inner_html());
 
             let selector =
                 scraper::Selector::parse(r#"td.lastpost>a.author"#).expect("BUG: illegal selector");
             let Some(last_post_username) = thread.select(&selector).next() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("missing last post author"));
                 };
+            let last_post_url = last_post_username
+                .value()
+                .attr("href")
+                .unwrap_or_default()
+                .to_owned();
             let last_post_username = last_post_username.inner_html();
 
             let selector =
@@ -89,20 +272,90 @@ impl Thread {
                 .map(|x| x.inner_html())
                 .unwrap_or(String::from("0"));
             let Ok(unread) = unread.parse() else {
-                    return Err(parsing_error);
+                    return Err(parsing_error("malformed unread count"));
                 };
 
+            let selector = scraper::Selector::parse(r#"td.star"#).expect("BUG: illegal selector");
+            let star = thread.select(&selector).next().and_then(|star| {
+                star.value()
+                    .classes()
+                    .find_map(|class| class.strip_prefix("bm")?.parse().ok())
+                    .and_then(BookmarkStar::from_category)
+            });
+
+            let selector = scraper::Selector::parse(r#"td.title div.title_pages a"#)
+                .expect("BUG: illegal selector");
+            let pages = thread
+                .select(&selector)
+                .filter_map(|x| scrape::trimmed_text(&x).parse().ok())
+                .max()
+                // The forums only leave out the pager for single page threads.
+                .unwrap_or(1);
+
+            let selector =
+                scraper::Selector::parse(r#"td.icon img"#).expect("BUG: illegal selector");
+            let icon = thread.select(&selector).next().map(parse_icon);
+            let selector =
+                scraper::Selector::parse(r#"td.icon2 img"#).expect("BUG: illegal selector");
+            let secondary_icon = thread.select(&selector).next().map(parse_icon);
+
+            let selector =
+                scraper::Selector::parse(r#"td.rating img"#).expect("BUG: illegal selector");
+            let rating = thread
+                .select(&selector)
+                .next()
+                .and_then(|x| x.value().attr("title"))
+                .and_then(Rating::parse);
+
+            let classes: Vec<_> = thread.value().classes().collect();
+            let selector =
+                scraper::Selector::parse(r#"td.title_sticky"#).expect("BUG: illegal selector");
+            let sticky = classes.contains(&"sticky") || thread.select(&selector).next().is_some();
+            let announcement = classes.contains(&"announcement");
+            let closed = classes.contains(&"closed");
+
             threads.push(Thread {
                 id: thread_id,
                 title,
+                author_id,
                 author_username,
                 replies,
                 views,
+                pages,
                 last_post_date,
                 last_post_username,
+                last_post_url,
                 unread,
+                star,
+                icon,
+                secondary_icon,
+                rating,
+                sticky,
+                announcement,
+                closed,
             });
         }
         Ok(threads)
     }
 }
+
+/// Parses a thread tag image. The tag ID is only available from the link that
+/// filters the forum by tag.
+fn parse_icon(image: scraper::ElementRef) -> PostIcon {
+    let id = image
+        .parent()
+        .and_then(scraper::ElementRef::wrap)
+        .and_then(|x| x.value().attr("href"))
+        .and_then(|href| scrape::query_param(href, "posticon"))
+        .unwrap_or_default();
+    let title = image
+        .value()
+        .attr("title")
+        .or(image.value().attr("alt"))
+        .unwrap_or_default();
+    PostIcon {
+        id,
+        title: title.to_owned(),
+        image_url: image.value().attr("src").unwrap_or_default().to_owned(),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{pagination::Pagination, post_list::Post, scrape, Error};
+
+/// Represents one page of a thread, along with metadata about the thread.
+#[derive(Debug)]
+pub struct ThreadPageView {
+    pub thread_id: String,
+    pub title: String,
+
+    /// The forum that the thread is in.
+    pub forum_id: String,
+
+    /// The forums above the thread, from the top-level category down to the
+    /// forum that the thread is in.
+    pub breadcrumbs: Vec<Breadcrumb>,
+
+    /// Which page this is, such as the page that ThreadPage::New resolved to.
+    pub pagination: Pagination,
+
+    pub posts: Vec<Post>,
+
+    /// Whether the thread is closed to new replies.
+    pub closed: bool,
+
+    /// Whether the thread has been archived. Archived threads are also closed.
+    pub archived: bool,
+
+    /// Whether the current user has bookmarked the thread.
+    pub bookmarked: bool,
+}
+
+/// References a forum above a thread.
+#[derive(Debug, Clone)]
+pub struct Breadcrumb {
+    pub forum_id: String,
+    pub title: String,
+}
+
+impl ThreadPageView {
+    /// Parses a page of a thread.
+    pub fn parse(document: &str) -> Result<ThreadPageView, Error> {
+        let posts = Post::parse_list(document)?;
+        let document = scraper::Html::parse_document(document);
+        let parsing_error = |reason: &str| Error::ThreadPageParsingError(reason.to_owned());
+
+        let selector = scraper::Selector::parse(r#"body"#).expect("BUG: illegal selector");
+        let Some(body) = document.select(&selector).next() else {
+            return Err(parsing_error("missing body"));
+        };
+        let Some(thread_id) = body.value().attr("data-thread") else {
+            return Err(parsing_error("missing thread ID"));
+        };
+        let Some(forum_id) = body.value().attr("data-forum") else {
+            return Err(parsing_error("missing forum ID"));
+        };
+
+        let selector =
+            scraper::Selector::parse(r#"div.breadcrumbs a"#).expect("BUG: illegal selector");
+        let mut breadcrumbs = Vec::new();
+        let mut title = None;
+        for link in document.select(&selector) {
+            let Some(href) = link.value().attr("href") else {
+                continue;
+            };
+            if link.value().classes().any(|x| x == "bclast") {
+                title = Some(scrape::trimmed_text(&link));
+            } else if let Some(forum_id) = scrape::query_param(href, "forumid") {
+                breadcrumbs.push(Breadcrumb {
+                    forum_id,
+                    title: scrape::trimmed_text(&link),
+                });
+            }
+        }
+        let Some(title) = title else {
+            return Err(parsing_error("missing thread title"));
+        };
+
+        let selector =
+            scraper::Selector::parse(r#"ul.postbuttons img"#).expect("BUG: illegal selector");
+        let buttons: Vec<_> = document
+            .select(&selector)
+            .filter_map(|x| x.value().attr("src"))
+            .collect();
+        let archived = buttons.iter().any(|x| x.contains("archive"));
+        let closed = archived || buttons.iter().any(|x| x.contains("closed"));
+
+        let selector = scraper::Selector::parse(r#".thread_bookmark.unbookmark"#)
+            .expect("BUG: illegal selector");
+        let bookmarked = document.select(&selector).next().is_some();
+
+        Ok(ThreadPageView {
+            thread_id: thread_id.to_owned(),
+            title,
+            forum_id: forum_id.to_owned(),
+            breadcrumbs,
+            pagination: Pagination::parse_html(&document),
+            posts,
+            closed,
+            archived,
+            bookmarked,
+        })
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use std::fmt;
+
+/// The timezone that the forums display times in unless the account is
+/// configured otherwise.
+pub const DEFAULT_TIMEZONE: chrono_tz::Tz = chrono_tz::America::Chicago;
+
+/// Formats that the forums use to display a date and time.
+const TIMESTAMP_FORMATS: [&str; 3] = [
+    // Posts, such as "Jun 18, 2023 22:08".
+    "%b %d, %Y %H:%M",
+    // Thread lists, such as "22:08 Jun 18, 2023".
+    "%H:%M %b %d, %Y",
+    // Edit notices, such as "22:08 on Jun 18, 2023".
+    "%H:%M on %b %d, %Y",
+];
+
+/// Formats that the forums use to display a date.
+const DATE_FORMATS: [&str; 1] = [
+    // Registration dates, such as "Jun 18, 2023".
+    "%b %d, %Y",
+];
+
+/// Represents a date and time as displayed by the forums.
+///
+/// The forums display wall clock times in the timezone chosen in the
+/// account's settings, without saying which timezone that is. The parsed time
+/// is therefore kept timezone-naive, and must be converted with the
+/// account's timezone, usually DEFAULT_TIMEZONE, to get an absolute time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timestamp {
+    /// The original text displayed by the forums.
+    pub raw: String,
+
+    /// The displayed wall clock time, or None if the text was not in a known
+    /// format.
+    pub local: Option<NaiveDateTime>,
+}
+
+impl Timestamp {
+    /// Parses a date and time displayed by the forums. Unknown formats are
+    /// preserved in raw without a parsed time.
+    pub fn parse(raw: &str) -> Timestamp {
+        let raw = raw.trim();
+        let local = TIMESTAMP_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok());
+        Timestamp {
+            raw: raw.to_owned(),
+            local,
+        }
+    }
+
+    /// Returns the absolute time, interpreting the displayed time in the
+    /// given timezone. Returns None if the time was not parsed or does not
+    /// exist in the timezone. Times that occur twice due to daylight saving
+    /// resolve to the earlier time.
+    pub fn with_timezone<Tz: TimeZone>(&self, timezone: &Tz) -> Option<DateTime<Tz>> {
+        timezone
+            .from_local_datetime(self.local.as_ref()?)
+            .earliest()
+    }
+
+    /// Returns the absolute time in UTC, interpreting the displayed time in
+    /// the given timezone.
+    pub fn to_utc<Tz: TimeZone>(&self, timezone: &Tz) -> Option<DateTime<Utc>> {
+        Some(self.with_timezone(timezone)?.with_timezone(&Utc))
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Represents a date as displayed by the forums.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Date {
+    /// The original text displayed by the forums.
+    pub raw: String,
+
+    /// The displayed date, or None if the text was not in a known format.
+    pub date: Option<NaiveDate>,
+}
+
+impl Date {
+    /// Parses a date displayed by the forums. Unknown formats are preserved in
+    /// raw without a parsed date.
+    pub fn parse(raw: &str) -> Date {
+        let raw = raw.trim();
+        let date = DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(raw, format).ok());
+        Date {
+            raw: raw.to_owned(),
+            date,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+/// Converts seconds since the Unix epoch, as returned by the forums' JSON
+/// endpoints, to a UTC time.
+pub(crate) fn from_unix(seconds: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(seconds, 0)
+}
@@ -0,0 +1,176 @@
+use something_awful::{
+    bbcode::{self, BBCode, ValidationIssue, Validator},
+    post_body::PostBody,
+    Error,
+};
+
+/// Asserts that a captured post body converts to the BBCode that was
+/// originally posted.
+fn assert_converts(html: &str, expected: &str) {
+    let converted = bbcode::from_html(html);
+    assert_eq!(converted, expected);
+    assert!(Validator::new().validate(&converted).is_empty());
+}
+
+#[test]
+fn converts_formatting() {
+    assert_converts(
+        include_str!("../fixtures/post_body/formatting.html"),
+        include_str!("../fixtures/post_body/formatting.bbcode"),
+    );
+}
+
+#[test]
+fn converts_quotes() {
+    assert_converts(
+        include_str!("../fixtures/post_body/quotes.html"),
+        include_str!("../fixtures/post_body/quotes.bbcode"),
+    );
+}
+
+#[test]
+fn converts_media() {
+    assert_converts(
+        include_str!("../fixtures/post_body/media.html"),
+        include_str!("../fixtures/post_body/media.bbcode"),
+    );
+}
+
+#[test]
+fn converts_blocks() {
+    assert_converts(
+        include_str!("../fixtures/post_body/blocks.html"),
+        include_str!("../fixtures/post_body/blocks.bbcode"),
+    );
+}
+
+#[test]
+fn omits_edit_notice() {
+    let body = PostBody::parse(include_str!("../fixtures/post_body/quotes.html"));
+    let edited = body.edited.as_ref().expect("edit notice not parsed");
+    assert_eq!(edited.username.as_deref(), Some("Somebody Cool"));
+    assert!(!bbcode::from_post_body(&body).contains("fucked around"));
+}
+
+#[test]
+fn builds_balanced_bbcode() {
+    let message = BBCode::new()
+        .text("Links:\n")
+        .list(vec![
+            BBCode::new().url("https://example.com/"),
+            BBCode::new().link("https://example.com/page", BBCode::new().text("a page")),
+        ])
+        .spoiler(BBCode::new().thumbnail("https://i.imgur.com/large.jpg"))
+        .build()
+        .unwrap();
+    assert_eq!(
+        message,
+        "Links:\n[list]\n[*][url]https://example.com/[/url]\n\
+         [*][url=https://example.com/page]a page[/url]\n[/list]\
+         [spoiler][timg]https://i.imgur.com/large.jpg[/timg][/spoiler]"
+    );
+    assert!(Validator::new().validate(&message).is_empty());
+}
+
+#[test]
+fn builds_literal_text() {
+    let message = BBCode::new()
+        .quote(BBCode::new().text("close with [/quote]"))
+        .build()
+        .unwrap();
+    assert_eq!(
+        message,
+        "[quote]close with [noparse][/quote][/noparse][/quote]"
+    );
+    assert!(Validator::new().validate(&message).is_empty());
+
+    let message = BBCode::new().text("[/noparse] [b]").build().unwrap();
+    assert_eq!(
+        message,
+        "[noparse][[/noparse][noparse]/noparse] [b][/noparse]"
+    );
+    assert!(Validator::new().validate(&message).is_empty());
+}
+
+#[test]
+fn builds_code_with_closing_tags() {
+    let message = BBCode::new().code("x [/code] y").build().unwrap();
+    assert_eq!(
+        message,
+        "[code]x [/code][noparse][/code][/noparse][code] y[/code]"
+    );
+    assert!(Validator::new().validate(&message).is_empty());
+}
+
+#[test]
+fn encodes_urls() {
+    let message = BBCode::new()
+        .url("https://example.com/?a[]=1")
+        .link("https://example.com/\"]", BBCode::new().text("link"))
+        .image("https://example.com/[/img].png")
+        .build()
+        .unwrap();
+    assert_eq!(
+        message,
+        "[url]https://example.com/?a%5B%5D=1[/url]\
+         [url=https://example.com/%22%5D]link[/url]\
+         [img]https://example.com/%5B/img%5D.png[/img]"
+    );
+    assert!(Validator::new().validate(&message).is_empty());
+}
+
+#[test]
+fn rejects_illegal_quote_authors() {
+    let result = BBCode::new()
+        .quote_user("Some\"body", BBCode::new().text("hi"))
+        .build();
+    let Err(Error::InvalidBBCode(issues)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert_eq!(
+        issues,
+        vec![ValidationIssue::IllegalAttribute {
+            tag: String::from("quote"),
+            value: String::from("Some\"body"),
+        }]
+    );
+
+    let result = BBCode::new()
+        .bold(BBCode::new().quote_post("Somebody", "1]", BBCode::new()))
+        .build();
+    assert!(result.is_err());
+}
+
+#[test]
+fn flags_unbalanced_tags() {
+    let issues = Validator::new().validate("[b][i]text[/b] more[/u]");
+    assert_eq!(
+        issues,
+        vec![
+            ValidationIssue::UnclosedTag {
+                tag: String::from("i"),
+                offset: 3,
+            },
+            ValidationIssue::UnexpectedClosingTag {
+                tag: String::from("u"),
+                offset: 19,
+            },
+        ]
+    );
+}
+
+#[test]
+fn ignores_tags_in_code() {
+    let issues = Validator::new().validate("[code]let x = a[0];[/b][/code] [*] [Caps]");
+    assert!(issues.is_empty());
+}
+
+#[test]
+fn flags_too_many_images() {
+    let validator = Validator::new().with_max_images(1);
+    let issues = validator.validate("[img]a.png[/img][TIMG]b.png[/TIMG]");
+    assert_eq!(
+        issues,
+        vec![ValidationIssue::TooManyImages { count: 2, limit: 1 }]
+    );
+}
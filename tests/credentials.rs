@@ -0,0 +1,106 @@
+use something_awful::{
+    client::User,
+    credentials::{CredentialStore, EncryptedFileStore, StoredAccount},
+    mock::{self, MockServer},
+    Error,
+};
+use std::path::PathBuf;
+
+/// Returns a path for a store file that does not exist yet.
+fn store_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "something-awful-{}-{name}.credentials",
+        std::process::id()
+    ));
+    std::fs::remove_file(&path).ok();
+    path
+}
+
+fn account(username: &str, password: Option<&str>) -> StoredAccount {
+    StoredAccount {
+        username: username.to_owned(),
+        session: format!("[\"session of {username}\"]"),
+        password: password.map(str::to_owned),
+    }
+}
+
+#[test]
+fn saves_accounts_encrypted() {
+    let path = store_path("saves");
+    let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+    store.save(&account("Personal", Some("hunter2"))).unwrap();
+    store.save(&account("Bot", None)).unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.contains("Personal"));
+    assert!(!contents.contains("session of"));
+    assert!(!contents.contains("hunter2"));
+
+    let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+    assert_eq!(store.accounts().unwrap(), ["Bot", "Personal"]);
+    let personal = store.load("Personal").unwrap().unwrap();
+    assert_eq!(personal.session, "[\"session of Personal\"]");
+    assert_eq!(personal.credentials().unwrap().password, "hunter2");
+    assert!(store.load("Bot").unwrap().unwrap().credentials().is_none());
+    assert!(store.load("Nobody").unwrap().is_none());
+
+    assert!(store.remove("Bot").unwrap());
+    assert!(!store.remove("Bot").unwrap());
+    let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+    assert_eq!(store.accounts().unwrap(), ["Personal"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn rejects_wrong_passphrase() {
+    let path = store_path("wrong");
+    let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+    store.save(&account("Personal", None)).unwrap();
+
+    let result = EncryptedFileStore::open(&path, "guess");
+    assert!(matches!(result, Err(Error::WrongPassphrase)));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn switches_accounts() {
+    let path = store_path("switches");
+    let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+    let server = MockServer::start().unwrap();
+    let client = server.client_builder().build().unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+    client.save_account(&store, mock::USERNAME, None).unwrap();
+
+    let client = server.client_builder().build().unwrap();
+    client.switch_account(&store, mock::USERNAME).await.unwrap();
+    let profile = client.fetch_profile(User::CurrentUser).await.unwrap();
+    assert_eq!(profile.unwrap().username, mock::USERNAME);
+
+    let result = client.switch_account(&store, "Nobody").await;
+    assert!(matches!(result, Err(Error::AccountNotFound(_))));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn logs_in_when_saved_session_is_missing() {
+    let path = store_path("logs_in");
+    let store = EncryptedFileStore::open(&path, "passphrase").unwrap();
+    store
+        .save(&StoredAccount {
+            username: mock::USERNAME.to_owned(),
+            session: String::new(),
+            password: Some(mock::PASSWORD.to_owned()),
+        })
+        .unwrap();
+
+    let server = MockServer::start().unwrap();
+    let client = server.client_builder().build().unwrap();
+    client.switch_account(&store, mock::USERNAME).await.unwrap();
+    assert_eq!(server.logins(), 1);
+    assert!(client
+        .fetch_profile(User::CurrentUser)
+        .await
+        .unwrap()
+        .is_some());
+    std::fs::remove_file(&path).unwrap();
+}
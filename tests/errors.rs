@@ -0,0 +1,56 @@
+use something_awful::{mock::fixtures, Error};
+use std::time::Duration;
+
+#[test]
+fn detects_standard_error_pages() {
+    assert!(matches!(
+        Error::from_error_page(fixtures::NOT_LOGGED_IN),
+        Some(Error::NotLoggedIn)
+    ));
+    assert!(matches!(
+        Error::from_error_page(fixtures::NO_PERMISSION),
+        Some(Error::NoPermission)
+    ));
+    assert!(matches!(
+        Error::from_error_page(fixtures::USER_NOT_FOUND),
+        Some(Error::UserNotFound)
+    ));
+    assert!(matches!(
+        Error::from_error_page(fixtures::THREAD_NOT_FOUND),
+        Some(Error::ThreadNotFound)
+    ));
+    assert!(matches!(
+        Error::from_error_page(fixtures::THREAD_CLOSED),
+        Some(Error::ThreadClosed)
+    ));
+    assert!(matches!(
+        Error::from_error_page(fixtures::DATABASE_ERROR),
+        Some(Error::DatabaseError)
+    ));
+}
+
+#[test]
+fn reads_probation_expiry() {
+    let Some(Error::Probation { until: Some(until) }) = Error::from_error_page(fixtures::PROBATION)
+    else {
+        panic!("probation not detected");
+    };
+    assert_eq!(until.raw, "Jun 25, 2023 22:08");
+    assert!(until.local.is_some());
+}
+
+#[test]
+fn reads_flood_control_wait() {
+    let Some(Error::FloodControl { wait }) = Error::from_error_page(fixtures::FLOOD_CONTROL) else {
+        panic!("flood control not detected");
+    };
+    assert_eq!(wait, Some(Duration::from_secs(42)));
+}
+
+#[test]
+fn ignores_other_pages() {
+    assert!(Error::from_error_page(fixtures::THREAD_PAGE_1).is_none());
+    assert!(Error::from_error_page(fixtures::BOOKMARKS).is_none());
+    assert!(Error::from_error_page(fixtures::NEW_REPLY).is_none());
+    assert!(Error::from_error_page(fixtures::PROFILE).is_none());
+}
@@ -0,0 +1,515 @@
+use chrono::NaiveDate;
+use futures::StreamExt;
+use something_awful::{
+    client::{Client, CookieStore, CookieStoreMutex, ThreadPage, User},
+    credentials::Credentials,
+    edit::Edit,
+    mock::{self, MockServer},
+    new_thread::{NewThread, Poll},
+    rate_limit::RateLimit,
+    reply::Reply,
+    retry::RetryPolicy,
+    search::SearchQuery,
+    thread_list::BookmarkStar,
+    Error,
+};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Starts a server and returns a client logged in to it.
+async fn logged_in() -> (MockServer, Client) {
+    let server = MockServer::start().unwrap();
+    let client = server
+        .client_builder()
+        .with_per_page(mock::POSTS_PER_PAGE)
+        .build()
+        .unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+    (server, client)
+}
+
+#[tokio::test]
+async fn rejects_wrong_password() {
+    let server = MockServer::start().unwrap();
+    let client = server.client_builder().build().unwrap();
+    assert!(client.login(mock::USERNAME, "wrong").await.is_err());
+}
+
+#[tokio::test]
+async fn shares_cookie_store() {
+    let server = MockServer::start().unwrap();
+    let cookie_store = Arc::new(CookieStoreMutex::new(CookieStore::new(None)));
+    let client = server
+        .client_builder()
+        .with_cookie_store(cookie_store.clone())
+        .build()
+        .unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+
+    let store = cookie_store.lock().unwrap();
+    let cookies = store.matches(server.base_url());
+    assert!(cookies.iter().any(|cookie| cookie.name() == "bbuserid"));
+}
+
+#[tokio::test]
+async fn fetches_profiles() {
+    let (_server, client) = logged_in().await;
+
+    let profile = client
+        .fetch_profile(User::CurrentUser)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(profile.username, mock::USERNAME);
+    assert_eq!(profile.userid.to_string(), mock::USER_ID);
+
+    let profile = client
+        .fetch_profile(User::Username("Nobody"))
+        .await
+        .unwrap();
+    assert!(profile.is_none());
+}
+
+#[tokio::test]
+async fn fetches_forum_index() {
+    let (_server, client) = logged_in().await;
+    let index = client.fetch_forum_index().await.unwrap();
+    assert_eq!(index.forums().len(), 5);
+}
+
+#[tokio::test]
+async fn searches_posts() {
+    let (server, client) = logged_in().await;
+
+    let query = SearchQuery::new("mock post")
+        .with_titles_only(true)
+        .with_author("Mock User")
+        .with_thread(mock::THREAD_ID)
+        .with_since(NaiveDate::from_ymd_opt(2023, 6, 1).unwrap())
+        .with_before(NaiveDate::from_ymd_opt(2023, 7, 1).unwrap())
+        .with_forum("1");
+    let results = client.search(query).await.unwrap();
+    assert_eq!(
+        server.searches(),
+        [
+            r#"intitle:"mock post" username:"Mock User" threadid:3900000 since:2023-06-01 before:2023-07-01"#
+        ]
+    );
+    assert_eq!(results.query_id, mock::QUERY_ID);
+    assert_eq!(results.results.len(), 2);
+
+    client.search(SearchQuery::new("mock")).await.unwrap();
+    assert_eq!(server.searches()[1], "mock");
+
+    let results = client
+        .fetch_search_results(&results.query_id, 2)
+        .await
+        .unwrap();
+    assert_eq!(results.results[0].post_id, "500000001");
+}
+
+#[tokio::test]
+async fn fetches_thread_pages() {
+    let (_server, client) = logged_in().await;
+
+    let page = client
+        .fetch_posts(mock::THREAD_ID, ThreadPage::Last)
+        .await
+        .unwrap();
+    assert_eq!(page.pagination.current_page, 2);
+    assert_eq!(page.posts[0].index, 3);
+}
+
+#[tokio::test]
+async fn streams_thread_posts() {
+    let (_server, client) = logged_in().await;
+
+    let posts: Vec<_> = client.thread_posts(mock::THREAD_ID, None).collect().await;
+    let indexes: Vec<_> = posts.into_iter().map(|x| x.unwrap().index).collect();
+    assert_eq!(indexes, [1, 2, 3, 4]);
+
+    let posts: Vec<_> = client
+        .thread_posts(mock::THREAD_ID, Some(2))
+        .collect()
+        .await;
+    let indexes: Vec<_> = posts.into_iter().map(|x| x.unwrap().index).collect();
+    assert_eq!(indexes, [2, 3, 4]);
+}
+
+#[tokio::test]
+async fn manages_bookmarks() {
+    let (server, client) = logged_in().await;
+
+    let threads = client.fetch_bookmarked_threads().await.unwrap();
+    assert_eq!(threads.len(), 2);
+
+    client.add_bookmark("3900002").await.unwrap();
+    client.remove_bookmark("3900001").await.unwrap();
+    assert_eq!(server.bookmarks(), ["3900000", "3900002"]);
+
+    client
+        .set_bookmark_star(mock::THREAD_ID, BookmarkStar::Cyan)
+        .await
+        .unwrap();
+    assert_eq!(
+        server.bookmark_star(mock::THREAD_ID),
+        Some(BookmarkStar::Cyan)
+    );
+}
+
+#[tokio::test]
+async fn marks_threads_and_forums_read() {
+    let (server, client) = logged_in().await;
+
+    client.mark_thread_unread(mock::THREAD_ID).await.unwrap();
+    client
+        .mark_thread_read_up_to(mock::THREAD_ID, 3)
+        .await
+        .unwrap();
+    client.mark_forum_read(mock::FORUM_ID).await.unwrap();
+
+    let marks = server.marks();
+    assert_eq!(marks.len(), 3);
+    let field = |i: usize, name: &str| marks[i].get(name).map(String::as_str);
+    assert_eq!(field(0, "action"), Some("resetseen"));
+    assert_eq!(field(0, "threadid"), Some(mock::THREAD_ID));
+    assert_eq!(field(1, "action"), Some("setseen"));
+    assert_eq!(field(1, "threadid"), Some(mock::THREAD_ID));
+    assert_eq!(field(1, "index"), Some("3"));
+    assert_eq!(field(2, "action"), Some("markread"));
+    assert_eq!(field(2, "forumid"), Some(mock::FORUM_ID));
+}
+
+#[tokio::test]
+async fn posts_replies() {
+    let (server, client) = logged_in().await;
+
+    let posted = client
+        .post_reply(mock::THREAD_ID, Reply::new("Hello from the tests"))
+        .await
+        .unwrap();
+    assert_eq!(server.replies(), ["Hello from the tests"]);
+    assert_eq!(posted.post_id, "500000005");
+    assert_eq!(posted.thread_id, mock::THREAD_ID);
+    assert_eq!(posted.page, Some(2));
+    assert_eq!(posted.index, Some(5));
+}
+
+#[tokio::test]
+async fn quotes_posts_in_order() {
+    let (server, client) = logged_in().await;
+
+    let quote = client.fetch_quote(&["500000002"]).await.unwrap();
+    assert_eq!(
+        quote,
+        "[quote=\"OtherUser\" post=\"500000002\"]\nThanks :v:\n[/quote]"
+    );
+
+    // The posts are on different pages, and are quoted in the order given.
+    let quote = client
+        .fetch_quote(&["500000003", "500000001"])
+        .await
+        .unwrap();
+    assert_eq!(
+        quote,
+        "[quote=\"MockUser\" post=\"500000003\"]\n\
+         Here is some code:\n\
+         [code]let ordered = a < b && b < c;[/code]\n\
+         [/quote]\n\
+         \n\
+         [quote=\"MockUser\" post=\"500000001\"]\n\
+         Welcome to the [b]mock thread[/b].\n\
+         It has four posts over two pages.\n\
+         [/quote]"
+    );
+
+    client
+        .post_reply(mock::THREAD_ID, Reply::quoting(&quote, "Agreed."))
+        .await
+        .unwrap();
+    assert_eq!(server.replies(), [format!("{quote}\n\nAgreed.")]);
+}
+
+#[tokio::test]
+async fn returns_reply_that_cannot_be_located() {
+    let server = MockServer::start().unwrap();
+    let client = server
+        .client_builder()
+        .with_retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+
+    // Fetching the form and posting succeed, but locating the post fails.
+    server.fail_requests_after(2, 1);
+    let posted = client
+        .post_reply(mock::THREAD_ID, Reply::new("Hello again"))
+        .await
+        .unwrap();
+    assert_eq!(server.replies(), ["Hello again"]);
+    assert_eq!(posted.post_id, "500000005");
+    assert_eq!(posted.thread_id, mock::THREAD_ID);
+    assert_eq!(posted.page, None);
+    assert_eq!(posted.index, None);
+}
+
+#[tokio::test]
+async fn reports_rejected_replies() {
+    let (server, client) = logged_in().await;
+
+    let result = client.post_reply(mock::THREAD_ID, Reply::new(" ")).await;
+    let Err(Error::ReplyNotPosted(reason)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert_eq!(reason, "The message you have entered is too short.");
+    assert!(server.replies().is_empty());
+}
+
+#[tokio::test]
+async fn edits_posts() {
+    let (server, client) = logged_in().await;
+
+    let source = client
+        .fetch_post_source(mock::EDITABLE_POST_ID)
+        .await
+        .unwrap();
+    assert_eq!(
+        source,
+        "Here is some code:\n[code]let ordered = a < b && b < c;[/code]"
+    );
+
+    client
+        .edit_post(mock::EDITABLE_POST_ID, Edit::new("No more code."))
+        .await
+        .unwrap();
+    client
+        .edit_post(
+            mock::EDITABLE_POST_ID,
+            Edit::new("Fixed a typo.")
+                .with_reason("typo")
+                .with_attachment("typo.txt", b"teh"),
+        )
+        .await
+        .unwrap();
+
+    let edits = server.edits();
+    assert_eq!(edits.len(), 2);
+    let field = |i: usize, name: &str| edits[i].get(name).map(String::as_str);
+    assert_eq!(field(0, "action"), Some("updatepost"));
+    assert_eq!(field(0, "form_cookie"), Some("5f4e3d2c1b0a"));
+    assert_eq!(field(0, "message"), Some("No more code."));
+    assert_eq!(field(0, "editreason"), None);
+    assert_eq!(field(0, "attachment"), None);
+    assert_eq!(field(1, "message"), Some("Fixed a typo."));
+    assert_eq!(field(1, "editreason"), Some("typo"));
+    assert_eq!(field(1, "attachment"), Some("teh"));
+}
+
+#[tokio::test]
+async fn rejects_edits_to_posts_by_others() {
+    let (server, client) = logged_in().await;
+
+    let result = client.edit_post("500000004", Edit::new("Mine now.")).await;
+    assert!(matches!(result, Err(Error::NoPermission)), "{result:?}");
+    assert!(server.edits().is_empty());
+}
+
+#[tokio::test]
+async fn posts_new_threads_with_polls() {
+    let (server, client) = logged_in().await;
+
+    let thread = NewThread::new("Mock poll", Reply::new("Vote below."))
+        .with_icon("31")
+        .with_poll(Poll::new("Best animal?", &["Pig", "Cat"]));
+    client
+        .post_new_thread(mock::FORUM_ID, thread)
+        .await
+        .unwrap();
+
+    let threads = server.new_threads();
+    assert_eq!(threads.len(), 1);
+    let field = |name: &str| threads[0].get(name).map(String::as_str);
+    assert_eq!(field("action"), Some("postthread"));
+    assert_eq!(field("form_cookie"), Some("0a1b2c3d4e5f"));
+    assert_eq!(field("subject"), Some("Mock poll"));
+    assert_eq!(field("iconid"), Some("31"));
+    assert_eq!(field("message"), Some("Vote below."));
+    assert_eq!(field("postpoll"), Some("yes"));
+    assert_eq!(field("polloptions"), Some("2"));
+
+    let polls = server.polls();
+    assert_eq!(polls.len(), 1);
+    let field = |name: &str| polls[0].get(name).map(String::as_str);
+    assert_eq!(field("threadid"), Some(mock::NEW_THREAD_ID));
+    assert_eq!(field("question"), Some("Best animal?"));
+    assert_eq!(field("options[1]"), Some("Pig"));
+    assert_eq!(field("options[2]"), Some("Cat"));
+    assert_eq!(field("multiple"), None);
+}
+
+#[tokio::test]
+async fn posts_new_threads_without_polls() {
+    let (server, client) = logged_in().await;
+
+    let thread = NewThread::new("Mock thread", Reply::new("Hello."));
+    client
+        .post_new_thread(mock::FORUM_ID, thread)
+        .await
+        .unwrap();
+
+    let threads = server.new_threads();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0]["iconid"], "0");
+    assert_eq!(threads[0].get("postpoll"), None);
+    assert!(server.polls().is_empty());
+}
+
+#[tokio::test]
+async fn rejects_invalid_new_threads() {
+    let (server, client) = logged_in().await;
+
+    let thread = NewThread::new("Mock poll", Reply::new("Vote below."))
+        .with_poll(Poll::new("Best animal?", &["Pig", " "]));
+    let result = client.post_new_thread(mock::FORUM_ID, thread).await;
+    assert!(matches!(result, Err(Error::InvalidPoll(_))), "{result:?}");
+
+    let thread = NewThread::new("Mock poll", Reply::new("Vote below."))
+        .with_poll(Poll::new("Best animal?", &[]));
+    let result = client.post_new_thread(mock::FORUM_ID, thread).await;
+    assert!(matches!(result, Err(Error::InvalidPoll(_))), "{result:?}");
+
+    let thread = NewThread::new("Mock thread", Reply::new("Hello.")).with_icon("1");
+    let result = client.post_new_thread(mock::FORUM_ID, thread).await;
+    assert!(
+        matches!(result, Err(Error::InvalidPostIcon(_))),
+        "{result:?}"
+    );
+
+    assert!(server.new_threads().is_empty());
+}
+
+#[tokio::test]
+async fn requires_login() {
+    let server = MockServer::start().unwrap();
+    let client = server.client_builder().build().unwrap();
+    let result = client.fetch_posts(mock::THREAD_ID, ThreadPage::First).await;
+    assert!(matches!(result, Err(Error::NotLoggedIn)));
+}
+
+#[tokio::test]
+async fn reports_missing_threads() {
+    let (_server, client) = logged_in().await;
+    let result = client.fetch_posts("1", ThreadPage::First).await;
+    assert!(matches!(result, Err(Error::ThreadNotFound)));
+}
+
+#[tokio::test]
+async fn reports_closed_threads() {
+    let (server, client) = logged_in().await;
+    let result = client
+        .post_reply(mock::CLOSED_THREAD_ID, Reply::new("Too late"))
+        .await;
+    assert!(matches!(result, Err(Error::ThreadClosed)));
+    assert!(server.replies().is_empty());
+}
+
+#[tokio::test]
+async fn retries_server_errors() {
+    let (server, client) = logged_in().await;
+
+    server.fail_next_requests(2);
+    let page = client.fetch_posts(mock::THREAD_ID, ThreadPage::First).await;
+    assert!(page.is_ok());
+}
+
+#[tokio::test]
+async fn gives_up_without_retries() {
+    let server = MockServer::start().unwrap();
+    let client = server
+        .client_builder()
+        .with_retry_policy(RetryPolicy::none())
+        .build()
+        .unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+
+    server.fail_next_requests(1);
+    let result = client.fetch_posts(mock::THREAD_ID, ThreadPage::First).await;
+    let Err(Error::ReqwestError(err)) = result else {
+        panic!("unexpected result: {result:?}");
+    };
+    assert_eq!(err.status().map(|x| x.as_u16()), Some(503));
+}
+
+#[tokio::test]
+async fn shares_rate_limit_between_clones() {
+    let server = MockServer::start().unwrap();
+    let client = server
+        .client_builder()
+        .with_rate_limit(RateLimit::new(1, Duration::from_millis(100)))
+        .build()
+        .unwrap();
+    let clone = client.clone();
+
+    let start = Instant::now();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+    clone.fetch_profile(User::CurrentUser).await.unwrap();
+    client.fetch_profile(User::CurrentUser).await.unwrap();
+    clone.fetch_profile(User::CurrentUser).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn reports_expired_sessions() {
+    let (server, client) = logged_in().await;
+    let mut saved = Vec::new();
+    client.save_credentials(&mut saved).unwrap();
+    server.expire_sessions();
+
+    let result = client.fetch_bookmarked_threads().await;
+    assert!(matches!(result, Err(Error::SessionExpired)));
+
+    let client = server.client_builder().build().unwrap();
+    client.load_credentials(saved.as_slice()).unwrap();
+    let result = client.fetch_profile(User::CurrentUser).await;
+    assert!(matches!(result, Err(Error::SessionExpired)));
+}
+
+#[tokio::test]
+async fn logs_in_again_when_session_expires() {
+    let server = MockServer::start().unwrap();
+    let client = server
+        .client_builder()
+        .with_credentials_provider(|| Some(Credentials::new(mock::USERNAME, mock::PASSWORD)))
+        .build()
+        .unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+    server.expire_sessions();
+
+    // Concurrent requests on the expired session share one new login.
+    let clone = client.clone();
+    let (threads, profile) = futures::join!(
+        client.fetch_bookmarked_threads(),
+        clone.fetch_profile(User::CurrentUser)
+    );
+    assert_eq!(threads.unwrap().len(), 2);
+    assert!(profile.unwrap().is_some());
+    assert_eq!(server.logins(), 2);
+}
+
+#[tokio::test]
+async fn reports_expired_sessions_without_credentials() {
+    let server = MockServer::start().unwrap();
+    let client = server
+        .client_builder()
+        .with_credentials_provider(|| None)
+        .build()
+        .unwrap();
+    client.login(mock::USERNAME, mock::PASSWORD).await.unwrap();
+    server.expire_sessions();
+
+    let result = client.fetch_profile(User::CurrentUser).await;
+    assert!(matches!(result, Err(Error::SessionExpired)));
+    assert_eq!(server.logins(), 1);
+}
@@ -0,0 +1,358 @@
+use something_awful::{
+    edit::EditParams,
+    forum_index::ForumIndex,
+    mock::fixtures,
+    new_thread::{NewThreadParams, Poll, PollParams},
+    pagination::Pagination,
+    pm::{
+        Folder, FolderAction, FolderParams, Message, MessageParams, MessageStatus, MessageSummary,
+    },
+    post_list::Post,
+    reply::ReplyParams,
+    search::SearchResults,
+    thread_list::{BookmarkStar, Thread},
+    thread_page::ThreadPageView,
+};
+
+#[test]
+fn parses_posts() {
+    let posts = Post::parse_list(fixtures::THREAD_PAGE_1).unwrap();
+    assert_eq!(posts.len(), 2);
+
+    let post = &posts[0];
+    assert_eq!(post.id, "500000001");
+    assert_eq!(post.index, 1);
+    assert_eq!(post.author_id.as_deref(), Some("1234"));
+    assert_eq!(post.author_username, "MockUser");
+    assert!(post.author_platinum);
+    assert_eq!(
+        post.author_avatar_url.as_deref(),
+        Some("https://fi.somethingawful.com/customtitles/mockuser.png")
+    );
+    assert_eq!(post.post_date.raw, "Jun 18, 2023 22:08");
+    assert!(post.post_date.local.is_some());
+    assert!(post.seen);
+
+    let post = &posts[1];
+    assert_eq!(post.author_username, "OtherUser");
+    assert!(post.author_moderator);
+    assert!(post.edited.is_none());
+}
+
+#[test]
+fn parses_posts_without_author_id() {
+    let page = fixtures::THREAD_PAGE_1.replacen("userinfo userid-1234", "userinfo", 1);
+    let posts = Post::parse_list(&page).unwrap();
+    assert_eq!(posts.len(), 2);
+    assert_eq!(posts[0].author_id, None);
+    assert_eq!(posts[0].author_username, "MockUser");
+    assert_eq!(posts[1].author_id.as_deref(), Some("5678"));
+}
+
+#[test]
+fn parses_edited_posts() {
+    let posts = Post::parse_list(fixtures::THREAD_PAGE_2).unwrap();
+    let edited = posts[1].edited.as_ref().unwrap();
+    assert_eq!(edited.username.as_deref(), Some("OtherUser"));
+    assert!(!posts[1].seen);
+}
+
+#[test]
+fn parses_thread_page() {
+    let page = ThreadPageView::parse(fixtures::THREAD_PAGE_2).unwrap();
+    assert_eq!(page.thread_id, "3900000");
+    assert_eq!(page.forum_id, "219");
+    assert_eq!(page.title, "Mock thread");
+    assert_eq!(page.breadcrumbs.len(), 2);
+    assert_eq!(
+        page.pagination,
+        Pagination {
+            current_page: 2,
+            total_pages: 2
+        }
+    );
+    assert!(page.bookmarked);
+    assert!(!page.closed);
+}
+
+#[test]
+fn parses_threads() {
+    let threads = Thread::parse_list(fixtures::BOOKMARKS).unwrap();
+    assert_eq!(threads.len(), 2);
+
+    let thread = &threads[0];
+    assert_eq!(thread.id, "3900000");
+    assert_eq!(thread.title, "Mock thread");
+    assert_eq!(thread.author_id, "1234");
+    assert_eq!(thread.replies, 3);
+    assert_eq!(thread.views, 57);
+    assert_eq!(thread.pages, 2);
+    assert_eq!(thread.unread, 1);
+    assert_eq!(thread.star, Some(BookmarkStar::Orange));
+    assert_eq!(thread.icon.as_ref().unwrap().id, "692");
+    assert_eq!(thread.rating.unwrap().votes, 12);
+
+    let thread = &threads[1];
+    assert_eq!(thread.star, Some(BookmarkStar::Yellow));
+    assert_eq!(thread.pages, 1);
+    assert!(thread.closed);
+    assert!(thread.rating.is_none());
+}
+
+#[test]
+fn counts_pages_from_pager() {
+    // Threads without a pager have one page, however many replies they have
+    // at whatever page size the account uses.
+    let bookmarks =
+        fixtures::BOOKMARKS.replace(r#"threadid=3900001">0</a>"#, r#"threadid=3900001">100</a>"#);
+    let threads = Thread::parse_list(&bookmarks).unwrap();
+    assert_eq!(threads[1].replies, 100);
+    assert_eq!(threads[1].pages, 1);
+}
+
+#[test]
+fn parses_forum_index() {
+    let index = ForumIndex::parse(fixtures::FORUM_INDEX).unwrap();
+    let categories: Vec<_> = index
+        .categories
+        .iter()
+        .map(|x| (x.id.as_str(), x.title.as_str(), x.forums.len()))
+        .collect();
+    assert_eq!(categories, [("48", "Main", 2), ("51", "Discussion", 1)]);
+
+    let forum = &index.categories[0].forums[0];
+    assert_eq!(forum.id, "1");
+    assert_eq!(forum.title, "General Bullshit");
+    assert_eq!(
+        forum.description,
+        "This forum is for the discussion of general bullshit."
+    );
+    let moderators: Vec<_> = forum
+        .moderators
+        .iter()
+        .map(|x| (x.user_id.as_str(), x.username.as_str()))
+        .collect();
+    assert_eq!(moderators, [("1001", "Mod One"), ("1002", "Mod Two")]);
+    let subforums: Vec<_> = forum
+        .subforums
+        .iter()
+        .map(|x| (x.id.as_str(), x.title.as_str()))
+        .collect();
+    assert_eq!(
+        subforums,
+        [
+            ("155", "SA's Front Page Discussion"),
+            ("214", "E/N Bullshit")
+        ]
+    );
+
+    let forum = &index.categories[0].forums[1];
+    assert_eq!(forum.title, "FYAD");
+    assert!(forum.description.is_empty());
+    assert!(forum.moderators.is_empty());
+    assert!(forum.subforums.is_empty());
+
+    let ids: Vec<_> = index.forums().iter().map(|x| x.id.as_str()).collect();
+    assert_eq!(ids, ["1", "155", "214", "26", "44"]);
+}
+
+#[test]
+fn parses_private_message_folders() {
+    let messages = MessageSummary::parse_list(fixtures::PRIVATE_MESSAGES).unwrap();
+    assert_eq!(messages.len(), 2);
+
+    let message = &messages[0];
+    assert_eq!(message.id, "70000002");
+    assert_eq!(message.title, "Re: Mock thread");
+    assert_eq!(message.correspondent_username, "OtherUser");
+    assert_eq!(message.date.raw, "Jun 19, 2023 01:15");
+    assert!(message.date.local.is_some());
+    assert_eq!(message.status, MessageStatus::Unread);
+    assert_eq!(messages[1].status, MessageStatus::Replied);
+}
+
+#[test]
+fn parses_private_messages() {
+    let message = Message::parse(fixtures::PRIVATE_MESSAGE, "70000002").unwrap();
+    assert_eq!(message.id, "70000002");
+    assert_eq!(message.title, "Re: Mock thread");
+    assert_eq!(message.sender_username, "OtherUser");
+    assert_eq!(message.date.raw, "Jun 19, 2023 01:15");
+    assert!(message.body.contains("Thanks for the <b>reply</b>."));
+
+    let page = fixtures::PRIVATE_MESSAGE.replacen(
+        ">Re: Mock thread</a>",
+        ">Re: Mock thread &gt; other threads</a>",
+        1,
+    );
+    let message = Message::parse(&page, "70000002").unwrap();
+    assert_eq!(message.title, "Re: Mock thread > other threads");
+}
+
+#[test]
+fn parses_private_message_form() {
+    let params = MessageParams::parse(fixtures::NEW_PRIVATE_MESSAGE).unwrap();
+    let message = params.prefilled("Any time.").unwrap();
+    let form = params.into_form(message.with_save_copy(false));
+    let field = |name: &str| {
+        form.iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    };
+    assert_eq!(field("action"), Some("dosend"));
+    assert_eq!(field("formkey"), Some("0123456789abcdef0123456789abcdef"));
+    assert_eq!(field("form_cookie"), Some("abcdef012345"));
+    assert_eq!(field("prevmessageid"), Some("70000002"));
+    assert_eq!(field("touser"), Some("OtherUser"));
+    assert_eq!(field("title"), Some("Re: Re: Mock thread"));
+    assert_eq!(field("message"), Some("Any time."));
+    assert_eq!(field("savecopy"), Some("no"));
+}
+
+#[test]
+fn parses_private_message_folder_form() {
+    let params = FolderParams::parse(fixtures::PRIVATE_MESSAGES).unwrap();
+    let form = params.into_form(&["70000001"], FolderAction::Move(Folder::Custom(3)));
+    let form: Vec<_> = form
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(
+        form,
+        [
+            ("action", "dostuff"),
+            ("thisfolder", "0"),
+            ("privatemessage[70000001]", "yes"),
+            ("folderid", "3"),
+            ("move", "Move"),
+        ]
+    );
+
+    // A folder ID already in the form is replaced rather than duplicated.
+    let page = fixtures::PRIVATE_MESSAGES.replacen(
+        "<input",
+        r#"<input type="hidden" name="folderid" value="0"><input"#,
+        1,
+    );
+    let params = FolderParams::parse(&page).unwrap();
+    let form = params.into_form(&["70000001"], FolderAction::Move(Folder::Custom(3)));
+    let folder_ids: Vec<_> = form
+        .iter()
+        .filter(|(key, _)| key == "folderid")
+        .map(|(_, value)| value.as_str())
+        .collect();
+    assert_eq!(folder_ids, ["3"]);
+}
+
+#[test]
+fn parses_search_results() {
+    let results = SearchResults::parse(fixtures::SEARCH_RESULTS, "8800001").unwrap();
+    assert_eq!(results.query_id, "8800001");
+    assert_eq!(
+        results.pagination,
+        Pagination {
+            current_page: 1,
+            total_pages: 2
+        }
+    );
+    assert_eq!(results.results.len(), 2);
+
+    let result = &results.results[0];
+    assert_eq!(result.post_id, "500000001");
+    assert_eq!(result.thread_id, "3900000");
+    assert_eq!(result.thread_title, "Mock thread");
+    assert_eq!(result.forum_id, "1");
+    assert_eq!(result.author_username, "MockUser");
+    assert_eq!(result.post_date.raw, "Jun 18, 2023 22:08");
+    assert_eq!(
+        result.snippet,
+        r#"This is a <em class="match">mock</em> post."#
+    );
+    assert_eq!(results.results[1].forum_id, "44");
+}
+
+#[test]
+fn parses_reply_form() {
+    let params = ReplyParams::parse(fixtures::NEW_REPLY).unwrap();
+    assert_eq!(params.prefilled_message(), "");
+}
+
+#[test]
+fn parses_edit_form() {
+    let params = EditParams::parse(fixtures::EDIT_POST).unwrap();
+    assert_eq!(
+        params.message,
+        "Here is some code:\n[code]let ordered = a < b && b < c;[/code]"
+    );
+    assert!(EditParams::parse(fixtures::NEW_REPLY).is_err());
+}
+
+#[test]
+fn parses_new_thread_form() {
+    let params = NewThreadParams::parse(fixtures::NEW_THREAD).unwrap();
+    let icons: Vec<_> = params
+        .icons
+        .iter()
+        .map(|x| (x.id.as_str(), x.title.as_str(), x.image_url.as_str()))
+        .collect();
+    assert_eq!(
+        icons,
+        [
+            ("0", "", ""),
+            (
+                "31",
+                "Pig",
+                "https://fi.somethingawful.com/forums/posticons/icon-31-pig.gif"
+            ),
+            (
+                "692",
+                "Question",
+                "https://fi.somethingawful.com/forums/posticons/icon-question.gif"
+            ),
+        ]
+    );
+    assert!(NewThreadParams::parse(fixtures::NEW_REPLY).is_err());
+}
+
+#[test]
+fn parses_poll_form() {
+    let params = PollParams::parse(fixtures::NEW_POLL).unwrap();
+    let poll = Poll::new("Best animal?", &["Pig", "Cat"])
+        .with_multiple_choice(true)
+        .with_timeout_days(7);
+    let form = params.into_form(poll);
+    let form: Vec<_> = form
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    assert_eq!(
+        form,
+        [
+            ("action", "postpoll"),
+            ("threadid", "3900002"),
+            ("formkey", "0123456789abcdef0123456789abcdef"),
+            ("question", "Best animal?"),
+            ("polloptions", "2"),
+            ("options[1]", "Pig"),
+            ("options[2]", "Cat"),
+            ("multiple", "yes"),
+            ("timeout", "7"),
+            ("submit", "Submit New Poll"),
+        ]
+    );
+    assert!(PollParams::parse(fixtures::NEW_THREAD).is_err());
+}
+
+#[test]
+fn parses_quoting_reply_form() {
+    let params = ReplyParams::parse(fixtures::NEW_REPLY_QUOTE).unwrap();
+    assert_eq!(
+        params.prefilled_message(),
+        "[quote=\"OtherUser\" post=\"500000002\"]\nThanks :v:\n[/quote]\n"
+    );
+}
+
+#[test]
+fn rejects_pages_without_reply_form() {
+    assert!(ReplyParams::parse(fixtures::NOT_LOGGED_IN).is_err());
+}
@@ -0,0 +1,124 @@
+use something_awful::post_body::{CodeKind, Node, PostBody};
+
+fn text(text: &str) -> Node {
+    Node::Text(text.to_owned())
+}
+
+#[test]
+fn parses_formatting() {
+    let body = PostBody::parse(include_str!("../fixtures/post_body/formatting.html"));
+    let [Node::Paragraph(nodes)] = body.nodes.as_slice() else {
+        panic!("unexpected nodes: {:?}", body.nodes);
+    };
+    assert_eq!(nodes[0], text("Hello "));
+    assert_eq!(nodes[1], Node::Bold(vec![text("bold")]));
+    assert!(nodes.contains(&Node::Fixed(vec![text("fixed")])));
+    assert!(nodes.contains(&Node::Spoiler(vec![text("spoiler")])));
+    assert!(nodes.contains(&text("A new paragraph & an ampersand.")));
+    assert!(nodes.contains(&text("Type [b]bold[/b] to get ")));
+    assert!(body.edited.is_none());
+}
+
+#[test]
+fn parses_quotes() {
+    let body = PostBody::parse(include_str!("../fixtures/post_body/quotes.html"));
+    assert_eq!(
+        body.nodes,
+        [
+            Node::Quote {
+                author: Some(String::from("Somebody Cool")),
+                post_id: Some(String::from("532441890")),
+                children: vec![
+                    Node::Quote {
+                        author: None,
+                        post_id: None,
+                        children: vec![text("an anonymous quote"), Node::LineBreak],
+                    },
+                    text("and a reply"),
+                    Node::LineBreak,
+                ],
+            },
+            Node::Paragraph(vec![
+                Node::LineBreak,
+                text("I agree "),
+                Node::Smilie {
+                    code: String::from(":v:"),
+                    url: String::from("https://fi.somethingawful.com/images/smilies/emot-v.gif"),
+                },
+                Node::LineBreak,
+            ]),
+            Node::Quote {
+                author: Some(String::from("Nobody")),
+                post_id: None,
+                children: vec![text("no link"), Node::LineBreak],
+            },
+        ]
+    );
+
+    let edited = body.edited.expect("edit notice not parsed");
+    assert_eq!(edited.username.as_deref(), Some("Somebody Cool"));
+    assert_eq!(
+        edited.text,
+        "Somebody Cool fucked around with this message at 04:26 on Jun 19, 2023"
+    );
+    let date = edited.date.expect("edit date not parsed");
+    assert_eq!(date.raw, "04:26 on Jun 19, 2023");
+    assert!(date.local.is_some());
+}
+
+#[test]
+fn parses_media() {
+    let body = PostBody::parse(include_str!("../fixtures/post_body/media.html"));
+    let [Node::Paragraph(nodes), video] = body.nodes.as_slice() else {
+        panic!("unexpected nodes: {:?}", body.nodes);
+    };
+    assert!(nodes.contains(&Node::Image {
+        url: String::from("https://i.imgur.com/abcdefg.png"),
+        thumbnail: false,
+    }));
+    assert!(nodes.contains(&Node::Image {
+        url: String::from("https://i.imgur.com/large.jpg"),
+        thumbnail: true,
+    }));
+    assert!(nodes.contains(&Node::Link {
+        url: String::from("https://example.com/page"),
+        children: vec![text("a "), Node::Bold(vec![text("named")]), text(" link")],
+    }));
+    assert_eq!(
+        *video,
+        Node::Video {
+            url: String::from("https://www.youtube.com/embed/dQw4w9WgXcQ?rel=0"),
+        }
+    );
+}
+
+#[test]
+fn parses_blocks() {
+    let body = PostBody::parse(include_str!("../fixtures/post_body/blocks.html"));
+    assert_eq!(
+        body.nodes,
+        [
+            Node::Paragraph(vec![text("Some code:"), Node::LineBreak]),
+            Node::Code {
+                kind: CodeKind::Code,
+                text: String::from("fn main() {\n    println!(\"<hello>\");\n}"),
+            },
+            Node::Paragraph(vec![text("A list:"), Node::LineBreak]),
+            Node::List {
+                ordered: false,
+                items: vec![
+                    vec![text("first")],
+                    vec![text("second "), Node::Bold(vec![text("item")])],
+                ],
+            },
+            Node::List {
+                ordered: true,
+                items: vec![vec![text("one")]],
+            },
+            Node::Code {
+                kind: CodeKind::Code,
+                text: String::from("let end = \"[/code]\";"),
+            },
+        ]
+    );
+}
@@ -0,0 +1,76 @@
+use chrono::{NaiveDate, TimeZone, Utc};
+use something_awful::timestamp::{Date, Timestamp, DEFAULT_TIMEZONE};
+
+#[test]
+fn parses_timestamp_formats() {
+    let expected = NaiveDate::from_ymd_opt(2023, 6, 18)
+        .unwrap()
+        .and_hms_opt(22, 8, 0);
+    for raw in [
+        "Jun 18, 2023 22:08",
+        "22:08 Jun 18, 2023",
+        "22:08 on Jun 18, 2023",
+    ] {
+        let timestamp = Timestamp::parse(&format!(" {raw}\n"));
+        assert_eq!(timestamp.raw, raw);
+        assert_eq!(timestamp.local, expected, "{raw}");
+        assert_eq!(timestamp.to_string(), raw);
+    }
+}
+
+#[test]
+fn preserves_unknown_timestamps() {
+    let timestamp = Timestamp::parse("Yesterday at 10 PM");
+    assert_eq!(timestamp.raw, "Yesterday at 10 PM");
+    assert_eq!(timestamp.local, None);
+    assert_eq!(timestamp.to_utc(&DEFAULT_TIMEZONE), None);
+}
+
+#[test]
+fn parses_dates() {
+    let date = Date::parse("Jun 18, 2003");
+    assert_eq!(date.date, NaiveDate::from_ymd_opt(2003, 6, 18));
+    assert_eq!(date.to_string(), "Jun 18, 2003");
+
+    let date = Date::parse("June 2003");
+    assert_eq!(date.raw, "June 2003");
+    assert_eq!(date.date, None);
+}
+
+#[test]
+fn converts_to_timezone() {
+    let timestamp = Timestamp::parse("Jun 18, 2023 22:08");
+    let local = timestamp.with_timezone(&DEFAULT_TIMEZONE).unwrap();
+    assert_eq!(local.to_rfc3339(), "2023-06-18T22:08:00-05:00");
+    assert_eq!(
+        timestamp.to_utc(&DEFAULT_TIMEZONE),
+        Some(Utc.with_ymd_and_hms(2023, 6, 19, 3, 8, 0).unwrap())
+    );
+    assert_eq!(
+        timestamp.to_utc(&chrono_tz::UTC),
+        Some(Utc.with_ymd_and_hms(2023, 6, 18, 22, 8, 0).unwrap())
+    );
+}
+
+#[test]
+fn resolves_repeated_times_to_the_earlier_time() {
+    // Clocks in Chicago fell back from 02:00 CDT to 01:00 CST, so 01:30
+    // happened twice.
+    let timestamp = Timestamp::parse("Nov 05, 2023 01:30");
+    let local = timestamp.with_timezone(&DEFAULT_TIMEZONE).unwrap();
+    assert_eq!(local.to_rfc3339(), "2023-11-05T01:30:00-05:00");
+    assert_eq!(
+        timestamp.to_utc(&DEFAULT_TIMEZONE),
+        Some(Utc.with_ymd_and_hms(2023, 11, 5, 6, 30, 0).unwrap())
+    );
+}
+
+#[test]
+fn rejects_skipped_times() {
+    // Clocks in Chicago sprang forward from 02:00 CST to 03:00 CDT, so 02:30
+    // never happened.
+    let timestamp = Timestamp::parse("Mar 12, 2023 02:30");
+    assert!(timestamp.local.is_some());
+    assert_eq!(timestamp.with_timezone(&DEFAULT_TIMEZONE), None);
+    assert_eq!(timestamp.to_utc(&DEFAULT_TIMEZONE), None);
+}